@@ -0,0 +1,482 @@
+//! Project configuration.
+//!
+//! The configuration is looked up next to the workspace root manifest,
+//! in this order:
+//!
+//! 1. `.fmt-toml.toml`
+//! 2. `fmt-toml.toml`
+//! 3. `[workspace.metadata.fmt-toml]` in the root `Cargo.toml`
+//!
+//! Every setting is optional; missing settings fall back to the built-in
+//! house style.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{
+    Context,
+    Result,
+    bail,
+};
+use serde::Deserialize;
+
+use crate::workspace::manifest_dir;
+
+/// File names that hold a dedicated configuration, in lookup order.
+const CONFIG_FILE_NAMES: [&str; 2] = [".fmt-toml.toml", "fmt-toml.toml"];
+
+/// Identifiers of the formatting rules that can be switched on or off
+/// through the `rules` table.
+pub const RULE_IDS: [&str; 16] = [
+    "internal-dependencies",
+    "hoist-dependencies",
+    "inherit-package-fields",
+    "simplify-dependency-specs",
+    "collapse-nested-tables",
+    "expand-inline-tables",
+    "inheritance-style",
+    "reorder-sections",
+    "package-order",
+    "workspace-order",
+    "dependency-key-order",
+    "sort-dependencies",
+    "sort-dependency-features",
+    "sort-features",
+    "wrap-arrays",
+    "layout",
+];
+
+/// Rules that only run when they are switched on explicitly.
+const OPT_IN_RULES: [&str; 3] = ["sort-features", "wrap-arrays", "layout"];
+
+/// Formatting configuration for a workspace.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct Config {
+    /// Order of the top-level sections. Sections that are not listed
+    /// follow in their original relative order.
+    pub section_order: Vec<String>,
+    /// Order of the keys in `[package]` and `[workspace.package]`. Keys
+    /// that are not listed follow in their original relative order;
+    /// `metadata` always comes last.
+    pub package_order: Vec<String>,
+    /// Order of the keys in `[workspace]`. Keys that are not listed
+    /// follow in their original relative order.
+    pub workspace_order: Vec<String>,
+    /// Order of the keys inside dependency specs. Keys that are not
+    /// listed follow in their original relative order.
+    pub dependency_key_order: Vec<String>,
+    /// How dependency tables are sorted.
+    pub dependency_sort: DependencySort,
+    /// How `[package]` fields and dependencies that inherit from the
+    /// workspace are written.
+    pub workspace_inheritance: InheritanceStyle,
+    /// Width of a `name = { ... }` dependency entry, with its arrays on
+    /// one line, above which the spec is written as a
+    /// `[dependencies.name]` table instead. Unlimited when unset.
+    pub inline_table_max_width: Option<usize>,
+    /// Number of keys above which a dependency spec is written as a
+    /// `[dependencies.name]` table instead of inline. Unlimited when
+    /// unset.
+    pub inline_table_max_keys: Option<usize>,
+    /// Options of the `layout` rule.
+    pub layout: LayoutConfig,
+    /// Rules switched on (`true`) or off (`false`) by id. Rules that are
+    /// not listed are enabled, except for the opt-in rules.
+    pub rules: BTreeMap<String, bool>,
+}
+
+/// How the `sort-dependencies` rule orders a dependency table.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DependencySort {
+    /// Sort the whole table as one block.
+    #[default]
+    Alphabetical,
+    /// Sort every group of entries separated by blank lines on its own,
+    /// keeping the comments above the first entry of a group at the top
+    /// of the group.
+    Grouped,
+}
+
+/// How an entry that inherits from the workspace is written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum InheritanceStyle {
+    /// `name = { workspace = true }`
+    #[default]
+    Inline,
+    /// `name.workspace = true`
+    Dotted,
+}
+
+/// Options of the `layout` rule, which runs taplo's formatter over the
+/// result of the structural rules.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct LayoutConfig {
+    /// Width after which arrays are expanded onto several lines. The
+    /// `wrap-arrays` rule uses the same width.
+    pub column_width: usize,
+    /// Indentation of the items of multi-line arrays.
+    pub indent_string: String,
+    /// Expand arrays that exceed `column-width`, one item per line.
+    pub array_auto_expand: bool,
+    /// Collapse multi-line arrays that fit within `column-width`.
+    pub array_auto_collapse: bool,
+    /// Put a trailing comma after the last item of multi-line arrays.
+    pub array_trailing_comma: bool,
+    /// Align the comments after consecutive entries.
+    pub align_comments: bool,
+    /// Maximum number of consecutive blank lines.
+    pub allowed_blank_lines: usize,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        Self {
+            column_width: 100,
+            indent_string: "    ".to_string(),
+            array_auto_expand: true,
+            array_auto_collapse: true,
+            array_trailing_comma: true,
+            align_comments: false,
+            allowed_blank_lines: 1,
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            section_order: to_strings(&[
+                "package",
+                "lib",
+                "bin",
+                "test",
+                "bench",
+                "example",
+                "dependencies",
+                "dev-dependencies",
+                "build-dependencies",
+                "target",
+                "features",
+            ]),
+            package_order: to_strings(&[
+                "name",
+                "description",
+                "version",
+                "edition",
+                "license",
+                "license-file",
+                "authors",
+                "rust-version",
+                "readme",
+                "homepage",
+                "repository",
+                "documentation",
+                "keywords",
+                "categories",
+                "publish",
+                "workspace",
+                "build",
+                "links",
+                "default-run",
+                "autolib",
+                "autobins",
+                "autoexamples",
+                "autotests",
+                "autobenches",
+                "resolver",
+                "include",
+                "exclude",
+                "metadata",
+            ]),
+            workspace_order: to_strings(&[
+                "resolver",
+                "members",
+                "default-members",
+                "exclude",
+                "package",
+                "dependencies",
+                "lints",
+                "metadata",
+            ]),
+            dependency_key_order: to_strings(&[
+                "workspace",
+                "version",
+                "path",
+                "git",
+                "branch",
+                "tag",
+                "rev",
+                "package",
+                "registry",
+                "default-features",
+                "features",
+                "optional",
+            ]),
+            dependency_sort: DependencySort::default(),
+            workspace_inheritance: InheritanceStyle::default(),
+            inline_table_max_width: None,
+            inline_table_max_keys: None,
+            layout: LayoutConfig::default(),
+            rules: BTreeMap::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Load the configuration for the workspace rooted at `root_dir`,
+    /// falling back to the defaults when none is found.
+    pub fn discover(root_dir: &Path) -> Result<Self> {
+        for name in CONFIG_FILE_NAMES {
+            let path = root_dir.join(name);
+            if path.is_file() {
+                let content =
+                    std::fs::read_to_string(&path).context(format!("Failed to read {:?}", path))?;
+                return Self::parse(&content)
+                    .context(format!("Invalid configuration in {:?}", path));
+            }
+        }
+
+        let manifest_path = root_dir.join("Cargo.toml");
+        if !manifest_path.is_file() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&manifest_path)
+            .context(format!("Failed to read {:?}", manifest_path))?;
+        Self::from_manifest(&content).context(format!(
+            "Invalid [workspace.metadata.fmt-toml] in {:?}",
+            manifest_path
+        ))
+    }
+
+    /// Find the configuration for a manifest in `start_dir` without
+    /// asking cargo for the workspace layout. The nearest ancestor that
+    /// holds a configuration file or a `[workspace]` manifest decides;
+    /// the defaults apply when there is none.
+    pub fn find(start_dir: &Path) -> Result<Self> {
+        let start_dir =
+            std::path::absolute(start_dir).context(format!("Failed to resolve {:?}", start_dir))?;
+        for dir in start_dir.ancestors() {
+            let has_config_file = CONFIG_FILE_NAMES
+                .iter()
+                .any(|name| dir.join(name).is_file());
+            if has_config_file || is_workspace_root(dir) {
+                return Self::discover(dir);
+            }
+        }
+        Ok(Self::default())
+    }
+
+    /// Find the configuration for the manifest at `manifest_path`,
+    /// starting from the directory that holds it.
+    pub fn for_manifest(manifest_path: &Path) -> Result<Self> {
+        Self::find(manifest_dir(manifest_path))
+    }
+
+    /// Parse a dedicated configuration file.
+    pub fn parse(content: &str) -> Result<Self> {
+        let config: Self = toml::from_str(content)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Read the configuration from `[workspace.metadata.fmt-toml]` of a
+    /// root manifest, falling back to the defaults when it is absent.
+    pub fn from_manifest(content: &str) -> Result<Self> {
+        let manifest: toml::Table = toml::from_str(content)?;
+        let Some(section) = manifest
+            .get("workspace")
+            .and_then(|w| w.get("metadata"))
+            .and_then(|m| m.get("fmt-toml"))
+        else {
+            return Ok(Self::default());
+        };
+        let config: Self = section.clone().try_into()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Whether the rule with the given id should run.
+    pub fn is_enabled(&self, rule: &str) -> bool {
+        self.rules
+            .get(rule)
+            .copied()
+            .unwrap_or(!OPT_IN_RULES.contains(&rule))
+    }
+
+    /// Narrow the rules down from the command line. When `only` is not
+    /// empty every rule it does not list is switched off; the rules in
+    /// `skip` are switched off on top of that.
+    pub fn select_rules(&mut self, only: &[String], skip: &[String]) -> Result<()> {
+        for rule in only.iter().chain(skip) {
+            check_rule(rule)?;
+        }
+        if !only.is_empty() {
+            for id in RULE_IDS {
+                self.rules
+                    .insert(id.to_string(), only.iter().any(|rule| rule == id));
+            }
+        }
+        for rule in skip {
+            self.rules.insert(rule.clone(), false);
+        }
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<()> {
+        for rule in self.rules.keys() {
+            check_rule(rule)?;
+        }
+        Ok(())
+    }
+}
+
+fn check_rule(rule: &str) -> Result<()> {
+    if !RULE_IDS.contains(&rule) {
+        bail!(
+            "unknown rule `{}` (known rules: {})",
+            rule,
+            RULE_IDS.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Whether `dir` holds a manifest with a `[workspace]` table.
+fn is_workspace_root(dir: &Path) -> bool {
+    std::fs::read_to_string(dir.join("Cargo.toml"))
+        .ok()
+        .and_then(|content| content.parse::<toml::Table>().ok())
+        .is_some_and(|manifest| manifest.contains_key("workspace"))
+}
+
+fn to_strings(items: &[&str]) -> Vec<String> {
+    items.iter().map(|item| item.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_when_metadata_is_absent() {
+        let config = Config::from_manifest("[workspace]\nmembers = []\n").expect("valid");
+        assert_eq!(config.section_order[0], "package");
+        assert_eq!(config.package_order[0], "name");
+        assert!(config.is_enabled("sort-dependencies"));
+        assert!(!config.is_enabled("sort-features"));
+        assert!(!config.is_enabled("wrap-arrays"));
+    }
+
+    #[test]
+    fn reads_workspace_metadata() {
+        let config = Config::from_manifest(
+            "\
+[workspace]
+members = []
+
+[workspace.metadata.fmt-toml]
+package-order = [\"name\", \"version\"]
+
+[workspace.metadata.fmt-toml.rules]
+collapse-nested-tables = false
+",
+        )
+        .expect("valid");
+        assert_eq!(config.package_order, ["name", "version"]);
+        assert_eq!(config.section_order, Config::default().section_order);
+        assert!(!config.is_enabled("collapse-nested-tables"));
+        assert!(config.is_enabled("reorder-sections"));
+    }
+
+    #[test]
+    fn rejects_unknown_settings_and_rules() {
+        assert!(Config::parse("section-ordr = []\n").is_err());
+        let err = Config::parse("[rules]\nsort-everything = true\n").unwrap_err();
+        assert!(
+            err.to_string().contains("unknown rule `sort-everything`"),
+            "unexpected error: {err}"
+        );
+    }
+
+    #[test]
+    fn select_rules_overrides_the_configuration() {
+        let mut config = Config::parse("[rules]\nreorder-sections = false\n").expect("valid");
+        config
+            .select_rules(
+                &[
+                    "reorder-sections".to_string(),
+                    "sort-dependencies".to_string(),
+                ],
+                &["sort-dependencies".to_string()],
+            )
+            .expect("known rules");
+        assert!(config.is_enabled("reorder-sections"));
+        assert!(!config.is_enabled("sort-dependencies"));
+        assert!(!config.is_enabled("collapse-nested-tables"));
+
+        let err = Config::default()
+            .select_rules(&[], &["sort".to_string()])
+            .unwrap_err();
+        assert!(err.to_string().contains("unknown rule `sort`"), "{err}");
+    }
+
+    #[test]
+    fn config_file_takes_precedence_over_metadata() {
+        let dir = std::env::temp_dir().join(format!("fmt-toml-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("create temp dir");
+        std::fs::write(
+            dir.join("Cargo.toml"),
+            "[workspace.metadata.fmt-toml]\nsection-order = [\"workspace\"]\n",
+        )
+        .expect("write manifest");
+        std::fs::write(
+            dir.join("fmt-toml.toml"),
+            "section-order = [\"package\", \"features\"]\n",
+        )
+        .expect("write config");
+
+        let config = Config::discover(&dir);
+        std::fs::remove_dir_all(&dir).expect("remove temp dir");
+
+        assert_eq!(
+            config.expect("valid").section_order,
+            ["package", "features"]
+        );
+    }
+
+    #[test]
+    fn find_walks_up_to_the_workspace_root() {
+        let dir = std::env::temp_dir().join(format!("fmt-toml-find-{}", std::process::id()));
+        let member_dir = dir.join("crates").join("app");
+        std::fs::create_dir_all(&member_dir).expect("create temp dir");
+        std::fs::write(
+            dir.join("Cargo.toml"),
+            "\
+[workspace]
+members = [\"crates/*\"]
+
+[workspace.metadata.fmt-toml]
+package-order = [\"version\", \"name\"]
+",
+        )
+        .expect("write root manifest");
+        std::fs::write(member_dir.join("Cargo.toml"), "[package]\nname = \"app\"\n")
+            .expect("write member manifest");
+
+        let config = Config::find(&member_dir);
+        std::fs::remove_dir_all(&dir).expect("remove temp dir");
+
+        assert_eq!(config.expect("valid").package_order, ["version", "name"]);
+    }
+
+    #[test]
+    fn for_manifest_accepts_a_bare_file_name() {
+        // Tests run from the package root, next to its Cargo.toml.
+        let config = Config::for_manifest(Path::new("Cargo.toml")).expect("valid");
+        assert_eq!(config.section_order, Config::default().section_order);
+    }
+}
@@ -0,0 +1,86 @@
+//! Unified diff output for `--diff`.
+
+use std::path::Path;
+
+use similar::{
+    ChangeTag,
+    TextDiff,
+};
+
+const BOLD: &str = "\x1b[1m";
+const CYAN: &str = "\x1b[36m";
+const GREEN: &str = "\x1b[32m";
+const RED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[0m";
+
+/// Render a unified diff between the on-disk content of a manifest and
+/// its formatted output, optionally with ANSI colors.
+pub fn unified_diff(path: &Path, original: &str, formatted: &str, color: bool) -> String {
+    let paint = |style: &str, text: &str| {
+        if color {
+            format!("{style}{text}{RESET}")
+        } else {
+            text.to_string()
+        }
+    };
+
+    let diff = TextDiff::from_lines(original, formatted);
+    let mut output = String::new();
+    output.push_str(&paint(BOLD, &format!("--- {}", path.display())));
+    output.push('\n');
+    output.push_str(&paint(BOLD, &format!("+++ {}", path.display())));
+    output.push('\n');
+
+    for hunk in diff.unified_diff().context_radius(3).iter_hunks() {
+        output.push_str(&paint(CYAN, &hunk.header().to_string()));
+        output.push('\n');
+        for change in hunk.iter_changes() {
+            let line = change.value().trim_end_matches('\n');
+            let rendered = match change.tag() {
+                ChangeTag::Delete => paint(RED, &format!("-{line}")),
+                ChangeTag::Insert => paint(GREEN, &format!("+{line}")),
+                ChangeTag::Equal => format!(" {line}"),
+            };
+            output.push_str(&rendered);
+            output.push('\n');
+            if change.missing_newline() {
+                output.push_str("\\ No newline at end of file\n");
+            }
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_diff_shows_removed_and_added_lines() {
+        let original = "[dependencies]\ntokio = \"1\"\nanyhow = \"1\"\n";
+        let formatted = "[dependencies]\nanyhow = \"1\"\ntokio = \"1\"\n";
+        let diff = unified_diff(Path::new("crate-a/Cargo.toml"), original, formatted, false);
+
+        assert_eq!(
+            diff,
+            "\
+--- crate-a/Cargo.toml
++++ crate-a/Cargo.toml
+@@ -1,3 +1,3 @@
+ [dependencies]
++anyhow = \"1\"
+ tokio = \"1\"
+-anyhow = \"1\"
+"
+        );
+    }
+
+    #[test]
+    fn colored_diff_wraps_changes_in_ansi_codes() {
+        let diff = unified_diff(Path::new("Cargo.toml"), "a = 1\n", "a = 2\n", true);
+
+        assert!(diff.contains("\x1b[31m-a = 1\x1b[0m"), "diff:\n{diff}");
+        assert!(diff.contains("\x1b[32m+a = 2\x1b[0m"), "diff:\n{diff}");
+    }
+}
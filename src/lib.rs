@@ -0,0 +1,186 @@
+//! Format and normalize Cargo.toml files according to workspace
+//! standards.
+//!
+//! This crate enforces:
+//! 1. All dependency versions at workspace level
+//! 2. Internal dependencies use { workspace = true }
+//! 3. All dependencies sorted alphabetically
+//! 4. Consistent [package] section format
+//!
+//! The `cargo fmt-toml` binary is a thin wrapper around this library.
+//! Tooling such as an `xtask` can call [`format_str`] for a single
+//! manifest, or [`Workspace::load`] and [`Manifest::format`] to format
+//! a whole workspace:
+//!
+//! ```
+//! use cargo_fmt_toml::{
+//!     Config,
+//!     format_str,
+//! };
+//!
+//! let outcome = format_str(
+//!     "[dependencies]\nserde = \"1\"\nanyhow = \"1\"\n",
+//!     &Config::default(),
+//! )?;
+//! assert_eq!(
+//!     outcome.output,
+//!     "[dependencies]\nanyhow = \"1\"\nserde = \"1\"\n"
+//! );
+//! assert!(outcome.changed());
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+pub mod config;
+mod manifest;
+pub mod rules;
+mod workspace;
+
+use anyhow::{
+    Context,
+    Result,
+};
+pub use config::Config;
+pub use manifest::Manifest;
+pub use rules::Rule;
+use serde::Serialize;
+use toml_edit::DocumentMut;
+pub use workspace::{
+    Workspace,
+    WorkspaceRule,
+};
+
+/// A change made to a manifest by one rule.
+#[derive(Debug, Clone, Serialize)]
+pub struct Change {
+    /// Id of the rule that made the change.
+    pub rule: &'static str,
+    /// Number of edits the rule made.
+    pub count: usize,
+    /// Human-readable summary, as shown in the default output.
+    pub message: String,
+}
+
+/// A problem a rule found in a manifest but left for a human to fix.
+#[derive(Debug, Clone, Serialize)]
+pub struct Warning {
+    /// Id of the rule that found the problem.
+    pub rule: &'static str,
+    /// Human-readable description, as shown in the default output.
+    pub message: String,
+}
+
+/// Append a change to `changes` unless the rule did nothing.
+pub(crate) fn record(changes: &mut Vec<Change>, rule: &'static str, count: usize, message: &str) {
+    if count > 0 {
+        changes.push(Change {
+            rule,
+            count,
+            message: message.to_string(),
+        });
+    }
+}
+
+/// The result of formatting a manifest held in a string.
+#[derive(Debug, Clone)]
+pub struct FormatOutcome {
+    /// The formatted manifest.
+    pub output: String,
+    /// Changes made, one entry per rule that changed something.
+    pub changes: Vec<Change>,
+}
+
+impl FormatOutcome {
+    /// Whether formatting changed the manifest.
+    pub fn changed(&self) -> bool {
+        !self.changes.is_empty()
+    }
+}
+
+/// Format the manifest in `input`. Workspace-level rules need every
+/// member of the workspace and are not applied.
+pub fn format_str(input: &str, config: &Config) -> Result<FormatOutcome> {
+    let mut doc = input
+        .parse::<DocumentMut>()
+        .context("Failed to parse manifest")?;
+    let changes = format_document(&mut doc, config)?;
+    let output = doc.to_string();
+
+    // Validate the output is valid TOML before returning.
+    output
+        .parse::<DocumentMut>()
+        .context("Internal error: formatted output is not valid TOML")?;
+
+    Ok(FormatOutcome { output, changes })
+}
+
+/// Format a single manifest document in place and return the changes
+/// made, one entry per rule that changed something. The rules in
+/// [`rules::all`] run in order, skipping those the configuration
+/// disables.
+pub fn format_document(doc: &mut DocumentMut, config: &Config) -> Result<Vec<Change>> {
+    let mut changes = Vec::new();
+    for rule in rules::all() {
+        if config.is_enabled(rule.id()) {
+            let count = rule.apply(doc, config)?;
+            record(&mut changes, rule.id(), count, rule.message());
+        }
+    }
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_document_follows_configured_order_and_rules() {
+        let mut doc = "\
+[dependencies.serde]
+version = \"1.0\"
+
+[package]
+version = \"0.1.0\"
+name = \"test\"
+edition = \"2024\"
+"
+        .parse::<DocumentMut>()
+        .expect("valid TOML");
+        let config = Config::parse(
+            "\
+section-order = [\"dependencies\", \"package\"]
+package-order = [\"edition\", \"name\"]
+
+[rules]
+simplify-dependency-specs = false
+collapse-nested-tables = false
+",
+        )
+        .expect("valid config");
+        format_document(&mut doc, &config).expect("format succeeded");
+        let result = doc.to_string();
+
+        assert!(
+            result.contains("[dependencies.serde]"),
+            "collapse should be disabled in:\n{result}"
+        );
+        let deps_pos = result.find("[dependencies.serde]").expect("missing deps");
+        let pkg_pos = result.find("[package]").expect("missing [package]");
+        assert!(deps_pos < pkg_pos, "wrong section order in:\n{result}");
+        assert!(
+            result.contains("[package]\nedition = \"2024\"\nname = \"test\"\nversion = \"0.1.0\""),
+            "wrong [package] order in:\n{result}"
+        );
+    }
+
+    #[test]
+    fn format_str_reports_changes() {
+        let input = "[dependencies]\nserde = \"1\"\nanyhow = \"1\"\n";
+        let outcome = format_str(input, &Config::default()).expect("format succeeded");
+        assert!(outcome.changed());
+        assert_eq!(outcome.changes[0].rule, "sort-dependencies");
+
+        let again = format_str(&outcome.output, &Config::default()).expect("format succeeded");
+        assert!(!again.changed());
+        assert_eq!(again.output, outcome.output);
+    }
+}
@@ -0,0 +1,157 @@
+//! Manifests loaded into memory for formatting.
+
+use std::path::{
+    Path,
+    PathBuf,
+};
+
+use anyhow::{
+    Context,
+    Result,
+};
+use toml_edit::DocumentMut;
+
+use crate::{
+    Change,
+    Config,
+    Warning,
+    format_document,
+};
+
+/// A manifest loaded into memory. Nothing is written back to disk
+/// until every manifest in the workspace has been formatted.
+pub struct Manifest {
+    /// Where the manifest was read from.
+    pub path: PathBuf,
+    /// Content as read from disk, used to show what changed.
+    pub original: String,
+    /// The document the rules edit in place.
+    pub doc: DocumentMut,
+    /// Changes made so far, in the order the rules ran.
+    pub changes: Vec<Change>,
+    /// Problems the formatter found but left for a human to fix.
+    pub warnings: Vec<Warning>,
+    /// Formatted output, set once the manifest is known to change.
+    pub formatted: Option<String>,
+    /// Why formatting this manifest failed, if it did.
+    pub error: Option<anyhow::Error>,
+}
+
+impl Manifest {
+    /// Read and parse a manifest from disk.
+    pub fn load(manifest_path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(manifest_path)
+            .context(format!("Failed to read {:?}", manifest_path))?;
+        Self::parse(manifest_path, content)
+    }
+
+    /// Like [`Manifest::load`], but a manifest that cannot be read or
+    /// parsed is kept with the failure in `error`, so that it is reported
+    /// along with the others instead of ending the run.
+    pub fn open(manifest_path: &Path) -> Self {
+        Self::load(manifest_path).unwrap_or_else(|err| Self {
+            path: manifest_path.to_path_buf(),
+            original: String::new(),
+            doc: DocumentMut::new(),
+            changes: Vec::new(),
+            warnings: Vec::new(),
+            formatted: None,
+            error: Some(err),
+        })
+    }
+
+    /// Parse manifest content that was read from `manifest_path`.
+    pub fn parse(manifest_path: &Path, content: String) -> Result<Self> {
+        let doc = content
+            .parse::<DocumentMut>()
+            .context(format!("Failed to parse {:?}", manifest_path))?;
+
+        Ok(Self {
+            path: manifest_path.to_path_buf(),
+            original: content,
+            doc,
+            changes: Vec::new(),
+            warnings: Vec::new(),
+            formatted: None,
+            error: None,
+        })
+    }
+
+    /// Total number of edits made to this manifest.
+    pub fn change_count(&self) -> usize {
+        self.changes.iter().map(|c| c.count).sum()
+    }
+
+    /// Whether `--check` should fail for this manifest: it needs
+    /// formatting, or a `[package]` field differs from
+    /// `[workspace.package]`. Dependencies kept because they differ from
+    /// `[workspace.dependencies]` are reported but do not fail the check.
+    pub fn needs_attention(&self) -> bool {
+        self.change_count() > 0
+            || self
+                .warnings
+                .iter()
+                .any(|warning| warning.rule == "inherit-package-fields")
+    }
+
+    /// Run the per-manifest rules and, when anything changed, render
+    /// the result into `formatted`. Failures are kept in `error` so
+    /// that the other manifests can still be reported on.
+    pub fn format(&mut self, config: &Config) {
+        if self.error.is_some() {
+            return;
+        }
+        match format_document(&mut self.doc, config) {
+            Ok(changes) => self.changes.extend(changes),
+            Err(err) => self.error = Some(err),
+        }
+        if self.error.is_none() && self.change_count() > 0 {
+            match self.render() {
+                // Edits that cancel out leave the manifest as it was.
+                Ok(output) if output == self.original => self.changes.clear(),
+                Ok(output) => self.formatted = Some(output),
+                Err(err) => self.error = Some(err),
+            }
+        }
+    }
+
+    /// Serialize the formatted manifest, validating that the output is
+    /// still valid TOML. Does NOT write to disk.
+    pub fn render(&self) -> Result<String> {
+        let output = self.doc.to_string();
+
+        // Validate the output is valid TOML before returning.
+        // This prevents corrupting the file when an internal
+        // transformation produces invalid content.
+        output.parse::<DocumentMut>().context(format!(
+            "Internal error: formatted output for {:?} is not valid TOML. \
+             File was NOT modified. Please report this as a bug.",
+            self.path
+        ))?;
+
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits_that_cancel_out_are_not_reported() {
+        let mut manifest = Manifest::parse(
+            Path::new("Cargo.toml"),
+            "[package]\nname = \"x\"\n".to_string(),
+        )
+        .expect("valid TOML");
+        manifest.changes.push(Change {
+            rule: "inherit-package-fields",
+            count: 1,
+            message: "Inherited [package] fields from [workspace.package]".to_string(),
+        });
+        manifest.format(&Config::default());
+
+        assert!(manifest.formatted.is_none());
+        assert_eq!(manifest.change_count(), 0);
+    }
+}
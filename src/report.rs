@@ -0,0 +1,146 @@
+//! Machine-readable output for `--message-format json`.
+//!
+//! Every manifest produces one JSON object on its own line, followed by
+//! a single summary object:
+//!
+//! ```text
+//! {"type":"manifest","path":"crate-a/Cargo.toml","changed":true,"changes":[...],"warnings":[],"error":null}
+//! {"type":"summary","files":2,"files_changed":1,"total_changes":3,"warnings":0,"errors":0,"written":true}
+//! ```
+
+use std::path::Path;
+
+use anyhow::Result;
+use cargo_fmt_toml::{
+    Change,
+    Manifest,
+    Warning,
+};
+use serde::Serialize;
+
+use crate::diff;
+
+/// One line of JSON output.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Message<'a> {
+    /// The outcome for a single manifest.
+    Manifest {
+        path: &'a Path,
+        /// Whether the manifest was (or, without writing, would be)
+        /// changed.
+        changed: bool,
+        changes: &'a [Change],
+        warnings: &'a [Warning],
+        error: Option<String>,
+        /// Unified diff of the changes, present with `--diff`.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        diff: Option<String>,
+    },
+    /// Totals for the whole run.
+    Summary {
+        files: usize,
+        files_changed: usize,
+        total_changes: usize,
+        /// Problems left for a human to fix. Only those found by
+        /// `inherit-package-fields` fail `--check`.
+        warnings: usize,
+        errors: usize,
+        /// Whether any manifest was written to disk.
+        written: bool,
+    },
+}
+
+/// Print one JSON object per manifest followed by a summary object.
+pub fn print_json(manifests: &[Manifest], written: bool, with_diff: bool) -> Result<()> {
+    for manifest in manifests {
+        let message = manifest_message(manifest, with_diff);
+        println!("{}", serde_json::to_string(&message)?);
+    }
+
+    let summary = Message::Summary {
+        files: manifests.len(),
+        files_changed: manifests.iter().filter(|m| m.formatted.is_some()).count(),
+        total_changes: manifests.iter().map(Manifest::change_count).sum(),
+        warnings: manifests.iter().map(|m| m.warnings.len()).sum(),
+        errors: manifests.iter().filter(|m| m.error.is_some()).count(),
+        written,
+    };
+    println!("{}", serde_json::to_string(&summary)?);
+
+    Ok(())
+}
+
+fn manifest_message(manifest: &Manifest, with_diff: bool) -> Message<'_> {
+    let diff = match &manifest.formatted {
+        Some(output) if with_diff => Some(diff::unified_diff(
+            &manifest.path,
+            &manifest.original,
+            output,
+            false,
+        )),
+        _ => None,
+    };
+
+    Message::Manifest {
+        path: &manifest.path,
+        changed: manifest.formatted.is_some(),
+        changes: &manifest.changes,
+        warnings: &manifest.warnings,
+        error: manifest.error.as_ref().map(|err| format!("{:#}", err)),
+        diff,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use toml_edit::DocumentMut;
+
+    use super::*;
+
+    #[test]
+    fn manifest_message_lists_changes_per_rule() {
+        let manifest = Manifest {
+            path: PathBuf::from("crate-a/Cargo.toml"),
+            original: "[dependencies]\nb = \"1\"\na = \"1\"\n".to_string(),
+            doc: DocumentMut::new(),
+            changes: vec![Change {
+                rule: "sort-dependencies",
+                count: 1,
+                message: "Sorted dependencies alphabetically".to_string(),
+            }],
+            warnings: vec![Warning {
+                rule: "hoist-dependencies",
+                message: "Kept serde: it differs from [workspace.dependencies]".to_string(),
+            }],
+            formatted: Some("[dependencies]\na = \"1\"\nb = \"1\"\n".to_string()),
+            error: None,
+        };
+
+        let json = serde_json::to_value(manifest_message(&manifest, false)).expect("serializes");
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "type": "manifest",
+                "path": "crate-a/Cargo.toml",
+                "changed": true,
+                "changes": [{
+                    "rule": "sort-dependencies",
+                    "count": 1,
+                    "message": "Sorted dependencies alphabetically",
+                }],
+                "warnings": [{
+                    "rule": "hoist-dependencies",
+                    "message": "Kept serde: it differs from [workspace.dependencies]",
+                }],
+                "error": null,
+            })
+        );
+
+        let json = serde_json::to_value(manifest_message(&manifest, true)).expect("serializes");
+        let diff = json["diff"].as_str().expect("diff is present");
+        assert!(diff.contains("+a = \"1\""), "diff:\n{diff}");
+    }
+}
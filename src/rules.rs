@@ -0,0 +1,3547 @@
+//! The per-manifest formatting rules. Each rule edits a document in
+//! place and returns how many changes it made; [`all`] lists them in the
+//! order they run.
+
+use std::collections::BTreeMap;
+
+use anyhow::{
+    Context,
+    Result,
+};
+use toml_edit::{
+    Array,
+    Decor,
+    DocumentMut,
+    InlineTable,
+    Item,
+    KeyMut,
+    RawString,
+    Table,
+    TableLike,
+    Value,
+};
+
+use crate::Config;
+use crate::config::{
+    DependencySort,
+    InheritanceStyle,
+    LayoutConfig,
+};
+
+/// A formatting rule that rewrites a single manifest.
+pub trait Rule {
+    /// Identifier used in the `rules` table of the configuration and by
+    /// `--rules` and `--skip-rule`.
+    fn id(&self) -> &'static str;
+
+    /// One-line description of what the rule does.
+    fn description(&self) -> &'static str;
+
+    /// Summary recorded for a manifest the rule changed.
+    fn message(&self) -> &'static str;
+
+    /// Apply the rule to `doc` in place and return the number of
+    /// changes made.
+    fn apply(&self, doc: &mut DocumentMut, config: &Config) -> Result<usize>;
+}
+
+/// Every per-manifest rule, in the order they run.
+pub fn all() -> Vec<Box<dyn Rule>> {
+    vec![
+        Box::new(SimplifyDependencySpecs),
+        Box::new(CollapseNestedTables),
+        Box::new(ExpandInlineTables),
+        Box::new(ApplyInheritanceStyle),
+        Box::new(ReorderSections),
+        Box::new(PackageOrder),
+        Box::new(WorkspaceOrder),
+        Box::new(DependencyKeyOrder),
+        Box::new(SortDependencies),
+        Box::new(SortDependencyFeatures),
+        Box::new(SortFeatures),
+        Box::new(WrapArrays),
+        Box::new(Layout),
+    ]
+}
+
+/// Rewrites dependency specs into their simplest shape: a spec with
+/// only a version becomes a version string, and `default-features =
+/// true` and `optional = false` are dropped.
+pub struct SimplifyDependencySpecs;
+
+impl Rule for SimplifyDependencySpecs {
+    fn id(&self) -> &'static str {
+        "simplify-dependency-specs"
+    }
+
+    fn description(&self) -> &'static str {
+        "Simplify redundant dependency spec shapes"
+    }
+
+    fn message(&self) -> &'static str {
+        "Simplified dependency specs"
+    }
+
+    fn apply(&self, doc: &mut DocumentMut, _config: &Config) -> Result<usize> {
+        let mut changes = 0;
+        for deps in dependency_tables_mut(doc) {
+            changes += simplify_dependency_specs(deps);
+        }
+        if let Some(deps) = workspace_dependencies_mut(doc) {
+            changes += simplify_dependency_specs(deps);
+        }
+        Ok(changes)
+    }
+}
+
+/// Collapses `[dependencies.foo]` style sub-tables into inline tables.
+/// Dependency specs that would be too large to stay inline under
+/// `inline-table-max-width` or `inline-table-max-keys` are left alone.
+pub struct CollapseNestedTables;
+
+impl Rule for CollapseNestedTables {
+    fn id(&self) -> &'static str {
+        "collapse-nested-tables"
+    }
+
+    fn description(&self) -> &'static str {
+        "Collapse nested sub-tables into inline entries"
+    }
+
+    fn message(&self) -> &'static str {
+        "Collapsed nested tables into inline entries"
+    }
+
+    fn apply(&self, doc: &mut DocumentMut, config: &Config) -> Result<usize> {
+        collapse_nested_tables(doc, config)
+    }
+}
+
+/// Writes inline dependency specs that exceed `inline-table-max-width`
+/// or `inline-table-max-keys` as `[dependencies.foo]` tables, the
+/// inverse of [`CollapseNestedTables`].
+pub struct ExpandInlineTables;
+
+impl Rule for ExpandInlineTables {
+    fn id(&self) -> &'static str {
+        "expand-inline-tables"
+    }
+
+    fn description(&self) -> &'static str {
+        "Expand oversized inline dependency specs into sub-tables"
+    }
+
+    fn message(&self) -> &'static str {
+        "Expanded oversized inline dependencies into tables"
+    }
+
+    fn apply(&self, doc: &mut DocumentMut, config: &Config) -> Result<usize> {
+        let mut changes = 0;
+        for deps in dependency_tables_mut(doc) {
+            changes += expand_dependency_specs(deps, config);
+        }
+        if let Some(deps) = workspace_dependencies_mut(doc) {
+            changes += expand_dependency_specs(deps, config);
+        }
+        Ok(changes)
+    }
+}
+
+/// Writes the `[package]` fields and dependencies that inherit from the
+/// workspace in the configured `workspace-inheritance` style, either
+/// `key = { workspace = true }` or `key.workspace = true`.
+pub struct ApplyInheritanceStyle;
+
+impl Rule for ApplyInheritanceStyle {
+    fn id(&self) -> &'static str {
+        "inheritance-style"
+    }
+
+    fn description(&self) -> &'static str {
+        "Write workspace inheritance in the configured style"
+    }
+
+    fn message(&self) -> &'static str {
+        "Applied the workspace inheritance style"
+    }
+
+    fn apply(&self, doc: &mut DocumentMut, config: &Config) -> Result<usize> {
+        let style = config.workspace_inheritance;
+        let mut changes = 0;
+        if let Some(package) = doc.get_mut("package").and_then(Item::as_table_mut) {
+            changes += apply_inheritance_style(package, style);
+        }
+        for deps in dependency_tables_mut(doc) {
+            changes += apply_inheritance_style(deps, style);
+        }
+        Ok(changes)
+    }
+}
+
+/// Puts the top-level sections in the configured order.
+pub struct ReorderSections;
+
+impl Rule for ReorderSections {
+    fn id(&self) -> &'static str {
+        "reorder-sections"
+    }
+
+    fn description(&self) -> &'static str {
+        "Order the top-level sections as configured in section-order"
+    }
+
+    fn message(&self) -> &'static str {
+        "Reordered sections"
+    }
+
+    fn apply(&self, doc: &mut DocumentMut, config: &Config) -> Result<usize> {
+        reorder_sections(doc, &config.section_order)
+    }
+}
+
+/// Puts the keys of `[package]` and `[workspace.package]` in the
+/// configured order, with `metadata` last.
+pub struct PackageOrder;
+
+impl Rule for PackageOrder {
+    fn id(&self) -> &'static str {
+        "package-order"
+    }
+
+    fn description(&self) -> &'static str {
+        "Order the [package] and [workspace.package] keys as configured in package-order"
+    }
+
+    fn message(&self) -> &'static str {
+        "Reordered [package] section"
+    }
+
+    fn apply(&self, doc: &mut DocumentMut, config: &Config) -> Result<usize> {
+        format_package_section(doc, &config.package_order)
+    }
+}
+
+/// Puts the keys of `[workspace]` in the configured order and sorts
+/// and deduplicates its `members`, `default-members` and `exclude`
+/// lists.
+pub struct WorkspaceOrder;
+
+impl Rule for WorkspaceOrder {
+    fn id(&self) -> &'static str {
+        "workspace-order"
+    }
+
+    fn description(&self) -> &'static str {
+        "Order the [workspace] keys as configured in workspace-order and sort its member lists"
+    }
+
+    fn message(&self) -> &'static str {
+        "Reordered [workspace] section"
+    }
+
+    fn apply(&self, doc: &mut DocumentMut, config: &Config) -> Result<usize> {
+        let Some(workspace) = doc.get_mut("workspace").and_then(Item::as_table_mut) else {
+            return Ok(0);
+        };
+        let mut changes = order_workspace_keys(workspace, &config.workspace_order);
+        for key in WORKSPACE_PATH_LISTS {
+            if let Some(paths) = workspace.get_mut(key).and_then(Item::as_array_mut) {
+                changes += sort_array_entries(paths, |value| {
+                    value.as_str().unwrap_or_default().to_string()
+                });
+            }
+        }
+        Ok(changes)
+    }
+}
+
+/// Puts the keys inside dependency specs in the configured order.
+pub struct DependencyKeyOrder;
+
+impl Rule for DependencyKeyOrder {
+    fn id(&self) -> &'static str {
+        "dependency-key-order"
+    }
+
+    fn description(&self) -> &'static str {
+        "Order the keys inside dependency specs as configured in dependency-key-order"
+    }
+
+    fn message(&self) -> &'static str {
+        "Reordered keys in dependency specs"
+    }
+
+    fn apply(&self, doc: &mut DocumentMut, config: &Config) -> Result<usize> {
+        let mut changes = 0;
+        for deps in dependency_tables_mut(doc) {
+            changes += order_dependency_specs(deps, &config.dependency_key_order);
+        }
+        if let Some(deps) = workspace_dependencies_mut(doc) {
+            changes += order_dependency_specs(deps, &config.dependency_key_order);
+        }
+        Ok(changes)
+    }
+}
+
+/// Sorts every dependency table alphabetically, including the
+/// target-specific ones and `[workspace.dependencies]`, and orders the
+/// `[target.<cfg>]` tables. With `dependency-sort = "grouped"` each
+/// group of entries separated by blank lines is sorted on its own.
+pub struct SortDependencies;
+
+impl Rule for SortDependencies {
+    fn id(&self) -> &'static str {
+        "sort-dependencies"
+    }
+
+    fn description(&self) -> &'static str {
+        "Sort dependencies alphabetically"
+    }
+
+    fn message(&self) -> &'static str {
+        "Sorted dependencies alphabetically"
+    }
+
+    fn apply(&self, doc: &mut DocumentMut, config: &Config) -> Result<usize> {
+        let mut sorted = sort_targets(doc);
+        for deps in dependency_tables_mut(doc) {
+            sorted += sort_table_in_place(deps, config.dependency_sort)?;
+        }
+        if let Some(deps) = workspace_dependencies_mut(doc) {
+            sorted += sort_table_in_place(deps, config.dependency_sort)?;
+        }
+        Ok(sorted)
+    }
+}
+
+/// Sorts and deduplicates the `features` arrays of dependency specs.
+pub struct SortDependencyFeatures;
+
+impl Rule for SortDependencyFeatures {
+    fn id(&self) -> &'static str {
+        "sort-dependency-features"
+    }
+
+    fn description(&self) -> &'static str {
+        "Sort and deduplicate the features enabled on each dependency"
+    }
+
+    fn message(&self) -> &'static str {
+        "Sorted dependency features"
+    }
+
+    fn apply(&self, doc: &mut DocumentMut, _config: &Config) -> Result<usize> {
+        let mut changes = 0;
+        for deps in dependency_tables_mut(doc) {
+            changes += sort_spec_features(deps);
+        }
+        if let Some(deps) = workspace_dependencies_mut(doc) {
+            changes += sort_spec_features(deps);
+        }
+        Ok(changes)
+    }
+}
+
+/// Sorts `[features]`: feature names alphabetically with `default`
+/// first, and the entries of every feature by kind and name.
+pub struct SortFeatures;
+
+impl Rule for SortFeatures {
+    fn id(&self) -> &'static str {
+        "sort-features"
+    }
+
+    fn description(&self) -> &'static str {
+        "Sort [features] with default first, and deduplicate and sort each feature's entries"
+    }
+
+    fn message(&self) -> &'static str {
+        "Sorted [features]"
+    }
+
+    fn apply(&self, doc: &mut DocumentMut, _config: &Config) -> Result<usize> {
+        let Some(features) = doc.get_mut("features").and_then(|f| f.as_table_mut()) else {
+            return Ok(0);
+        };
+
+        let mut changes = 0;
+        for (_name, item) in features.iter_mut() {
+            if let Some(entries) = item.as_array_mut() {
+                changes += sort_feature_entries(entries);
+            }
+        }
+
+        let current_keys: Vec<String> = features.iter().map(|(k, _)| k.to_string()).collect();
+        let mut sorted_keys = current_keys.clone();
+        sorted_keys.sort_by_key(|key| (key != "default", key.clone()));
+        if current_keys != sorted_keys {
+            let mut entries = BTreeMap::new();
+            for key in &current_keys {
+                if let Some(entry) = features.remove_entry(key) {
+                    entries.insert(key.clone(), entry);
+                }
+            }
+            for key in &sorted_keys {
+                if let Some((key, item)) = entries.remove(key) {
+                    features.insert_formatted(&key, item);
+                }
+            }
+            changes += 1;
+        }
+
+        Ok(changes)
+    }
+}
+
+/// Dependency table names, both at the top level and under
+/// `[target.<cfg>]`.
+pub(crate) const DEPENDENCY_SECTIONS: [&str; 3] =
+    ["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// Lists of paths in `[workspace]` that are sorted and deduplicated.
+const WORKSPACE_PATH_LISTS: [&str; 3] = ["members", "default-members", "exclude"];
+
+/// Collect every dependency table of a manifest: `[dependencies]`,
+/// `[dev-dependencies]`, `[build-dependencies]` and their
+/// `[target.<cfg>.*]` counterparts.
+pub(crate) fn dependency_tables_mut(doc: &mut DocumentMut) -> Vec<&mut Table> {
+    let mut tables = Vec::new();
+    for (key, item) in doc.as_table_mut().iter_mut() {
+        if key.get() == "target" {
+            let targets = item.as_table_mut().into_iter().flat_map(|t| t.iter_mut());
+            for (_target_name, target_config) in targets {
+                if let Some(target_config) = target_config.as_table_mut() {
+                    tables.extend(dependency_sections_mut(target_config));
+                }
+            }
+        } else if DEPENDENCY_SECTIONS.contains(&key.get())
+            && let Some(table) = item.as_table_mut()
+        {
+            tables.push(table);
+        }
+    }
+    tables
+}
+
+fn dependency_sections_mut(table: &mut Table) -> Vec<&mut Table> {
+    table
+        .iter_mut()
+        .filter(|(key, _)| DEPENDENCY_SECTIONS.contains(&key.get()))
+        .filter_map(|(_, item)| item.as_table_mut())
+        .collect()
+}
+
+/// Puts arrays that do not fit within `layout.column-width` one item per
+/// line and collapses multi-line arrays that do fit back onto one line.
+/// The `layout` rule lays out arrays itself, so this rule does nothing
+/// when both are enabled.
+pub struct WrapArrays;
+
+impl Rule for WrapArrays {
+    fn id(&self) -> &'static str {
+        "wrap-arrays"
+    }
+
+    fn description(&self) -> &'static str {
+        "Wrap arrays wider than column-width and collapse arrays that fit"
+    }
+
+    fn message(&self) -> &'static str {
+        "Wrapped arrays"
+    }
+
+    fn apply(&self, doc: &mut DocumentMut, config: &Config) -> Result<usize> {
+        if config.is_enabled("layout") {
+            return Ok(0);
+        }
+        Ok(wrap_arrays_in(doc.as_table_mut(), config))
+    }
+}
+
+/// Normalizes whitespace with taplo's formatter: indentation, spacing
+/// around `=`, trailing commas, the final newline and blank lines
+/// between sections.
+pub struct Layout;
+
+impl Rule for Layout {
+    fn id(&self) -> &'static str {
+        "layout"
+    }
+
+    fn description(&self) -> &'static str {
+        "Normalize whitespace and array layout with taplo"
+    }
+
+    fn message(&self) -> &'static str {
+        "Normalized layout"
+    }
+
+    fn apply(&self, doc: &mut DocumentMut, config: &Config) -> Result<usize> {
+        let original = doc.to_string();
+        // Work on a copy so that a manifest that is already laid out is
+        // left exactly as it was.
+        let mut separated = doc.clone();
+        separate_tables(separated.as_table_mut());
+        let formatted =
+            taplo::formatter::format(&separated.to_string(), layout_options(&config.layout));
+        // The first table header gets a blank line like any other; drop
+        // it when the document starts with a table.
+        let formatted = formatted.trim_start_matches('\n');
+        if formatted == original {
+            return Ok(0);
+        }
+
+        *doc = formatted
+            .parse::<DocumentMut>()
+            .context("Internal error: layout output is not valid TOML")?;
+        Ok(1)
+    }
+}
+
+/// Wrap or collapse the arrays of every entry in `table` and its
+/// sub-tables.
+fn wrap_arrays_in(table: &mut Table, config: &Config) -> usize {
+    let mut changes = 0;
+    for (key, item) in table.iter_mut() {
+        match item {
+            Item::Value(value) => changes += wrap_entry(&key.display_repr(), value, config),
+            Item::Table(inner) => changes += wrap_arrays_in(inner, config),
+            Item::ArrayOfTables(array) => {
+                changes += array
+                    .iter_mut()
+                    .map(|inner| wrap_arrays_in(inner, config))
+                    .sum::<usize>();
+            }
+            Item::None => {}
+        }
+    }
+    changes
+}
+
+/// Lay out the arrays in the value of a `key = value` entry: on one line
+/// when the whole entry fits within `column-width`, one item per line
+/// otherwise. Arrays inside an inline table are wrapped in place. Arrays
+/// holding comments are left alone.
+fn wrap_entry(key: &str, value: &mut Value, config: &Config) -> usize {
+    if has_comments(value) {
+        return 0;
+    }
+    let before = value.to_string();
+
+    let mut single_line = value.clone();
+    collapse_arrays(&mut single_line);
+    if entry_width(key, &single_line) <= config.layout.column_width {
+        *value = single_line;
+    } else {
+        let indent = &config.layout.indent_string;
+        match value {
+            Value::Array(array) => expand_array(array, indent),
+            Value::InlineTable(inline) => {
+                for (_, inner) in inline.iter_mut() {
+                    if let Value::Array(array) = inner {
+                        expand_array(array, indent);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    usize::from(value.to_string() != before)
+}
+
+/// Width of a `key = value` entry, as written.
+fn entry_width(key: &str, value: &Value) -> usize {
+    key.chars().count() + " = ".len() + value.to_string().trim().chars().count()
+}
+
+/// Put every array in `value` on a single line.
+fn collapse_arrays(value: &mut Value) {
+    match value {
+        Value::Array(array) => {
+            array.iter_mut().for_each(collapse_arrays);
+            array.fmt();
+        }
+        Value::InlineTable(inline) => {
+            inline
+                .iter_mut()
+                .for_each(|(_, inner)| collapse_arrays(inner));
+        }
+        _ => {}
+    }
+}
+
+/// Put every item of `array` on its own line, with a trailing comma.
+fn expand_array(array: &mut Array, indent: &str) {
+    if array.is_empty() {
+        return;
+    }
+    for item in array.iter_mut() {
+        collapse_arrays(item);
+        item.decor_mut().set_prefix(format!("\n{indent}"));
+        item.decor_mut().set_suffix("");
+    }
+    array.set_trailing_comma(true);
+    array.set_trailing("\n");
+}
+
+/// Whether an array in `value` holds a comment.
+fn has_comments(value: &Value) -> bool {
+    match value {
+        Value::Array(array) => {
+            array.trailing().as_str().is_some_and(|t| t.contains('#'))
+                || array
+                    .iter()
+                    .any(|item| decor_has_comment(item.decor()) || has_comments(item))
+        }
+        Value::InlineTable(inline) => inline.iter().any(|(_, inner)| has_comments(inner)),
+        _ => false,
+    }
+}
+
+fn decor_has_comment(decor: &Decor) -> bool {
+    [decor.prefix(), decor.suffix()]
+        .into_iter()
+        .flatten()
+        .any(|raw| raw.as_str().is_some_and(|text| text.contains('#')))
+}
+
+/// The taplo formatter options for a layout configuration.
+fn layout_options(layout: &LayoutConfig) -> taplo::formatter::Options {
+    taplo::formatter::Options {
+        column_width: layout.column_width,
+        indent_string: layout.indent_string.clone(),
+        array_auto_expand: layout.array_auto_expand,
+        array_auto_collapse: layout.array_auto_collapse,
+        array_trailing_comma: layout.array_trailing_comma,
+        align_comments: layout.align_comments,
+        allowed_blank_lines: layout.allowed_blank_lines,
+        ..taplo::formatter::Options::default()
+    }
+}
+
+/// Put exactly one blank line in front of every table header, above
+/// its leading comments.
+fn separate_tables(table: &mut Table) {
+    for (_, item) in table.iter_mut() {
+        match item {
+            Item::Table(inner) => {
+                if !inner.is_implicit() && !inner.is_dotted() {
+                    separate_table(inner);
+                }
+                separate_tables(inner);
+            }
+            Item::ArrayOfTables(array) => array.iter_mut().for_each(|inner| {
+                separate_table(inner);
+                separate_tables(inner);
+            }),
+            _ => {}
+        }
+    }
+}
+
+fn separate_table(table: &mut Table) {
+    let prefix = table
+        .decor()
+        .prefix()
+        .and_then(|p| p.as_str())
+        .unwrap_or("")
+        .to_string();
+    let rest = &prefix[leading_blank_len(&prefix)..];
+    table.decor_mut().set_prefix(format!("\n{rest}"));
+}
+
+/// The `[workspace.dependencies]` table of a root manifest.
+fn workspace_dependencies_mut(doc: &mut DocumentMut) -> Option<&mut Table> {
+    doc.get_mut("workspace")?
+        .get_mut("dependencies")?
+        .as_table_mut()
+}
+
+fn collapse_nested_tables(doc: &mut DocumentMut, config: &Config) -> Result<usize> {
+    let mut changes = 0;
+
+    if let Some(package) = doc.get_mut("package").and_then(|p| p.as_table_mut()) {
+        let collapsed = collapse_table_entries(package, |_, _| true);
+        if collapsed > 0 {
+            changes += collapsed;
+        }
+    }
+
+    for deps in dependency_tables_mut(doc) {
+        changes += collapse_dependency_table(deps, config);
+    }
+    if let Some(deps) = workspace_dependencies_mut(doc) {
+        changes += collapse_dependency_table(deps, config);
+    }
+
+    Ok(changes)
+}
+
+/// Collapse the `[section.name]` sub-tables of a dependency table,
+/// except for specs that are too large to be written inline. The table
+/// itself becomes explicit so its header survives the collapse.
+fn collapse_dependency_table(deps: &mut Table, config: &Config) -> usize {
+    let collapsed = collapse_table_entries(deps, |key, value| {
+        !value
+            .as_inline_table()
+            .is_some_and(|spec| too_large_for_inline(key, spec, config))
+    });
+    if collapsed > 0 {
+        deps.set_implicit(false);
+    }
+    collapsed
+}
+
+/// Collapse the `[section.key]` sub-tables of `table` whose inline
+/// form `keep_inline` accepts.
+fn collapse_table_entries(table: &mut Table, keep_inline: impl Fn(&str, &Value) -> bool) -> usize {
+    let keys: Vec<String> = table.iter().map(|(k, _)| k.to_string()).collect();
+    let mut replacements: Vec<(String, Value)> = Vec::new();
+
+    for key in &keys {
+        let Some(Item::Table(inner)) = table.get(key) else {
+            continue;
+        };
+
+        if inner.is_dotted() {
+            continue;
+        }
+
+        if let Some(value) = inline_entry(inner)
+            && keep_inline(key, &value)
+        {
+            replacements.push((key.clone(), value));
+        }
+    }
+
+    let changes = replacements.len();
+    for (key, value) in replacements {
+        replace_with_value(table, &key, value);
+    }
+
+    changes
+}
+
+/// Build the inline table that replaces a `[section.name]` sub-table,
+/// or `None` when the sub-table holds more than plain values. Inline
+/// tables cannot hold comments, so comments on the entries inside move
+/// up to the leading comments of the collapsed entry.
+fn inline_entry(inner: &Table) -> Option<Value> {
+    let mut inline = InlineTable::new();
+    let mut comments = String::new();
+
+    for (child_key, child_item) in inner.iter() {
+        let mut value = child_item.as_value()?.clone();
+        if let Some(key) = inner.key(child_key) {
+            comments.push_str(&comment_lines(key.leaf_decor().prefix()));
+        }
+        comments.push_str(&comment_lines(value.decor().suffix()));
+        value.decor_mut().clear();
+        inline.insert(child_key, value);
+    }
+
+    let mut value = Value::InlineTable(inline);
+    value.decor_mut().set_prefix(comments);
+    Some(value)
+}
+
+/// The comment lines in a piece of decor, one per line, without the
+/// surrounding whitespace.
+fn comment_lines(raw: Option<&RawString>) -> String {
+    let Some(raw) = raw.and_then(|raw| raw.as_str()) else {
+        return String::new();
+    };
+    raw.lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with('#'))
+        .map(|line| format!("{line}\n"))
+        .collect()
+}
+
+/// Replace a table entry with a value, keeping the entry's position and
+/// its comments. Entries that used to be `[section.key]` tables render
+/// as `key = value` afterwards: the comments above the table header
+/// become the leading comments of the entry and a comment after the
+/// header becomes its trailing comment.
+///
+/// Comment lines in the prefix of `value` are placed above the entry,
+/// after its existing leading comments.
+pub(crate) fn replace_with_value(table: &mut Table, key: &str, mut value: Value) {
+    let Some(item) = table.get_mut(key) else {
+        return;
+    };
+    let extra_comments = comment_lines(value.decor().prefix());
+    value.decor_mut().clear();
+
+    let mut table_comments = None;
+    if let Item::Table(old) = item
+        && old.is_dotted()
+    {
+        // A dotted entry has no header: the blank lines in front of it
+        // sit in the prefix of its first key. Its comments come with
+        // `value`.
+        let first_prefix = old
+            .iter()
+            .next()
+            .and_then(|(k, _)| old.key(k))
+            .and_then(|k| k.leaf_decor().prefix())
+            .and_then(|p| p.as_str())
+            .unwrap_or("");
+        table_comments = Some(first_prefix[..leading_blank_len(first_prefix)].to_string());
+    } else if let Item::Table(old) = item {
+        table_comments = Some(comment_lines(old.decor().prefix()));
+        if let Some(suffix) = old.decor().suffix().and_then(|s| s.as_str())
+            && suffix.trim_start().starts_with('#')
+        {
+            value.decor_mut().set_suffix(suffix);
+        }
+    } else if let Some(old) = item.as_value() {
+        *value.decor_mut() = old.decor().clone();
+    }
+    *item = Item::Value(value);
+
+    let Some(mut key) = table.key_mut(key) else {
+        return;
+    };
+    let decor = key.leaf_decor_mut();
+    let leading = match table_comments {
+        Some(comments) => {
+            decor.clear();
+            comments
+        }
+        None => decor
+            .prefix()
+            .and_then(|p| p.as_str())
+            .unwrap_or("")
+            .to_string(),
+    };
+    if !leading.is_empty() || !extra_comments.is_empty() {
+        decor.set_prefix(format!("{leading}{extra_comments}"));
+    }
+}
+
+fn simplify_dependency_specs(deps: &mut Table) -> usize {
+    let keys: Vec<String> = deps.iter().map(|(k, _)| k.to_string()).collect();
+    let mut changes = 0;
+    for key in &keys {
+        if let Some(spec) = deps.get_mut(key).and_then(Item::as_table_like_mut) {
+            let mut removed = 0;
+            // Inherited specs keep `default-features = true`: it
+            // overrides `default-features = false` in the workspace.
+            if !spec
+                .get("workspace")
+                .and_then(Item::as_bool)
+                .unwrap_or(false)
+            {
+                removed += remove_redundant(spec, "default-features", true);
+            }
+            removed += remove_redundant(spec, "optional", false);
+            if removed > 0
+                && let Some(inline) = deps.get_mut(key).and_then(Item::as_inline_table_mut)
+            {
+                inline.fmt();
+            }
+            changes += removed;
+        }
+        changes += simplify_version_spec(deps, key);
+    }
+    if changes > 0 {
+        deps.set_implicit(false);
+    }
+    changes
+}
+
+/// Remove `key` from a dependency spec when it holds `redundant`, the
+/// value Cargo assumes anyway. Entries with comments and the last key
+/// of a spec are kept.
+fn remove_redundant(spec: &mut dyn TableLike, key: &str, redundant: bool) -> usize {
+    let Some((k, item)) = spec.get_key_value(key) else {
+        return 0;
+    };
+    let Some(value) = item.as_value() else {
+        return 0;
+    };
+    if value.as_bool() != Some(redundant)
+        || spec.len() == 1
+        || decor_has_comment(k.leaf_decor())
+        || decor_has_comment(value.decor())
+    {
+        return 0;
+    }
+    spec.remove(key);
+    1
+}
+
+/// Write a spec that only holds a version as a version string.
+fn simplify_version_spec(deps: &mut Table, key: &str) -> usize {
+    let spec = match deps.get(key) {
+        Some(Item::Value(value @ Value::InlineTable(_))) => value.clone(),
+        Some(Item::Table(table)) => match inline_entry(table) {
+            Some(value) => value,
+            None => return 0,
+        },
+        _ => return 0,
+    };
+    let Some(inline) = spec.as_inline_table() else {
+        return 0;
+    };
+    let keys: Vec<&str> = inline.iter().map(|(k, _)| k).collect();
+    if keys != ["version"] || has_comments(&spec) {
+        return 0;
+    }
+    let Some(mut version) = inline.get("version").cloned() else {
+        return 0;
+    };
+    *version.decor_mut() = Decor::new(
+        spec.decor().prefix().cloned().unwrap_or_default(),
+        RawString::default(),
+    );
+    replace_with_value(deps, key, version);
+    1
+}
+
+/// Write every entry of `table` that inherits from the workspace,
+/// `key = { workspace = true, ... }` or `key.workspace = true`, in the
+/// given style. Entries that are `[section.key]` tables are left to the
+/// collapse and expand rules.
+fn apply_inheritance_style(table: &mut Table, style: InheritanceStyle) -> usize {
+    let keys: Vec<String> = table.iter().map(|(k, _)| k.to_string()).collect();
+    let mut changes = 0;
+    for key in &keys {
+        let converted = match (style, table.get(key)) {
+            (InheritanceStyle::Dotted, Some(Item::Value(value @ Value::InlineTable(spec)))) => {
+                inherits(spec.get("workspace")) && !has_comments(value)
+            }
+            (InheritanceStyle::Inline, Some(Item::Table(spec))) => {
+                spec.is_dotted() && inherits(spec.get("workspace").and_then(Item::as_value))
+            }
+            _ => false,
+        };
+        if !converted {
+            continue;
+        }
+        match style {
+            InheritanceStyle::Inline => replace_dotted_with_inline(table, key),
+            InheritanceStyle::Dotted => replace_with_dotted(table, key),
+        }
+        changes += 1;
+    }
+    changes
+}
+
+/// Whether the `workspace` key of an entry is `true`.
+fn inherits(workspace: Option<&Value>) -> bool {
+    workspace.and_then(Value::as_bool) == Some(true)
+}
+
+/// Replace a dotted entry with an inline table. The comment after its
+/// last line stays after the entry.
+fn replace_dotted_with_inline(table: &mut Table, key: &str) {
+    let Some(Item::Table(dotted)) = table.get_mut(key) else {
+        return;
+    };
+    let mut trailing = None;
+    if let Some((_, Item::Value(last))) = dotted.iter_mut().last()
+        && let Some(suffix) = last.decor().suffix().and_then(|s| s.as_str())
+        && suffix.trim_start().starts_with('#')
+    {
+        trailing = Some(suffix.to_string());
+        last.decor_mut().set_suffix("");
+    }
+    let Some(value) = inline_entry(dotted) else {
+        return;
+    };
+
+    replace_with_value(table, key, value);
+    if let Some(trailing) = trailing
+        && let Some(value) = table.get_mut(key).and_then(Item::as_value_mut)
+    {
+        value.decor_mut().set_suffix(trailing);
+    }
+}
+
+/// Whether a dependency spec is too large to be written inline under
+/// `inline-table-max-keys` and `inline-table-max-width`. The width is
+/// measured with every array on one line, as `wrap-arrays` would wrap
+/// them otherwise.
+fn too_large_for_inline(key: &str, spec: &InlineTable, config: &Config) -> bool {
+    if config
+        .inline_table_max_keys
+        .is_some_and(|max_keys| spec.len() > max_keys)
+    {
+        return true;
+    }
+    config.inline_table_max_width.is_some_and(|max_width| {
+        let mut single_line = Value::InlineTable(spec.clone());
+        single_line.decor_mut().clear();
+        collapse_arrays(&mut single_line);
+        entry_width(key, &single_line) > max_width
+    })
+}
+
+/// Write the inline specs in `deps` that are too large to stay inline
+/// as `[section.name]` sub-tables.
+fn expand_dependency_specs(deps: &mut Table, config: &Config) -> usize {
+    let oversized: Vec<String> = deps
+        .iter()
+        .filter(|(key, item)| {
+            item.as_inline_table()
+                .is_some_and(|spec| too_large_for_inline(key, spec, config))
+        })
+        .map(|(key, _)| key.to_string())
+        .collect();
+
+    for key in &oversized {
+        replace_with_table(deps, key);
+    }
+    // A header left without entries would be written as an empty
+    // `[section]` above the new sub-tables.
+    let emptied = !oversized.is_empty() && deps.iter().all(|(_, item)| is_sub_table(item));
+    if emptied && !decor_has_comment(deps.decor()) {
+        deps.set_implicit(true);
+    }
+    oversized.len()
+}
+
+/// Replace an inline table entry with a `[section.key]` sub-table, the
+/// inverse of [`replace_with_value`]: the leading comments of the entry
+/// go above the table header and its trailing comment follows the
+/// header.
+fn replace_with_table(table: &mut Table, key: &str) {
+    let Some(Item::Value(Value::InlineTable(spec))) = table.get(key) else {
+        return;
+    };
+
+    let mut sub_table = Table::new();
+    for (child_key, value) in spec.iter() {
+        let mut value = value.clone();
+        value.decor_mut().clear();
+        sub_table.insert(child_key, Item::Value(value));
+    }
+    let leading = table
+        .key(key)
+        .map(|k| comment_lines(k.leaf_decor().prefix()))
+        .unwrap_or_default();
+    sub_table.decor_mut().set_prefix(format!("\n{leading}"));
+    if let Some(suffix) = spec.decor().suffix().and_then(|s| s.as_str())
+        && suffix.trim_start().starts_with('#')
+    {
+        sub_table.decor_mut().set_suffix(suffix);
+    }
+
+    table.insert(key, Item::Table(sub_table));
+    if let Some(mut key) = table.key_mut(key) {
+        key.leaf_decor_mut().clear();
+    }
+}
+
+/// Replace an inline table entry with dotted keys, `key.name = value`
+/// for every entry of the inline table. The leading comments of the
+/// entry stay above it and its trailing comment follows the last line.
+fn replace_with_dotted(table: &mut Table, key: &str) {
+    let Some(Item::Value(Value::InlineTable(spec))) = table.get(key) else {
+        return;
+    };
+
+    let mut dotted = Table::new();
+    dotted.set_dotted(true);
+    for (child_key, value) in spec.iter() {
+        let mut value = value.clone();
+        value.decor_mut().clear();
+        dotted.insert(child_key, Item::Value(value));
+    }
+    if let Some(suffix) = spec.decor().suffix().and_then(|s| s.as_str())
+        && suffix.trim_start().starts_with('#')
+        && let Some((_, Item::Value(last))) = dotted.iter_mut().last()
+    {
+        last.decor_mut().set_suffix(suffix);
+    }
+    // The leading decor of a dotted entry sits in front of its first
+    // key.
+    let leading = table
+        .key(key)
+        .and_then(|k| k.leaf_decor().prefix())
+        .cloned();
+    if let Some(leading) = leading
+        && let Some(mut first) = dotted.iter_mut().next().map(|(k, _)| k)
+    {
+        first.leaf_decor_mut().set_prefix(leading);
+    }
+
+    table.insert(key, Item::Table(dotted));
+    if let Some(mut key) = table.key_mut(key) {
+        key.leaf_decor_mut().clear();
+    }
+}
+
+fn reorder_sections(doc: &mut DocumentMut, section_order: &[String]) -> Result<usize> {
+    // Get current top-level keys from the document.  doc.iter()
+    // correctly identifies top-level keys including dotted sections
+    // like [workspace.package] grouped under "workspace".
+    let current_keys: Vec<String> = doc.iter().map(|(k, _)| k.to_string()).collect();
+
+    // Build expected order: ordered sections first, then any extra
+    // sections (workspace, profile, lints, patch, etc.) in their
+    // original relative order.
+    let mut expected_keys = Vec::new();
+    for section in section_order {
+        if current_keys.contains(section) {
+            expected_keys.push(section.clone());
+        }
+    }
+    for key in &current_keys {
+        if !section_order.contains(key) {
+            expected_keys.push(key.clone());
+        }
+    }
+
+    // Check if reordering is needed.
+    if current_keys == expected_keys {
+        return Ok(0);
+    }
+
+    // Serialize each top-level key individually and reassemble in
+    // the desired order.  We use toml_edit's own serialization per
+    // key, which correctly handles dotted sub-sections, inline
+    // tables, array-of-tables, multi-line values, and comments.
+    //
+    // For each key we build a temporary document containing only
+    // that key, serialize it, and collect the text fragment.
+    let mut section_fragments: std::collections::HashMap<String, String> =
+        std::collections::HashMap::new();
+
+    // Remove all entries from the original document.
+    let table = doc.as_table_mut();
+    let mut entries: Vec<(toml_edit::Key, Item)> = Vec::new();
+    let keys_to_remove: Vec<String> = table.iter().map(|(k, _)| k.to_string()).collect();
+    for key in &keys_to_remove {
+        if let Some(entry) = table.remove_entry(key) {
+            entries.push(entry);
+        }
+    }
+
+    // Serialize each key individually.
+    for (key, item) in entries {
+        let key_name = key.to_string();
+        let mut tmp_doc = DocumentMut::new();
+        tmp_doc.insert_formatted(&key, item);
+        section_fragments.insert(key_name, tmp_doc.to_string());
+    }
+
+    // Reassemble in the desired order.
+    let mut new_content = String::new();
+    for key_name in &expected_keys {
+        if let Some(fragment) = section_fragments.get(key_name) {
+            if !new_content.is_empty() && !new_content.ends_with("\n\n") {
+                // Ensure a blank line between sections.
+                if !new_content.ends_with('\n') {
+                    new_content.push('\n');
+                }
+                new_content.push('\n');
+            }
+            new_content.push_str(fragment.trim_start());
+        }
+    }
+
+    // Ensure trailing newline.
+    if !new_content.ends_with('\n') {
+        new_content.push('\n');
+    }
+
+    // Parse the reordered content back into the document.
+    *doc = new_content
+        .parse::<DocumentMut>()
+        .context("Internal error: reordered output is not valid TOML")?;
+
+    Ok(1)
+}
+
+fn format_package_section(doc: &mut DocumentMut, desired_order: &[String]) -> Result<usize> {
+    let mut changes = 0;
+
+    if let Some(package) = doc.get_mut("package").and_then(|p| p.as_table_mut()) {
+        changes += order_package_keys(package, desired_order);
+    }
+    if let Some(package) = doc
+        .get_mut("workspace")
+        .and_then(|w| w.get_mut("package"))
+        .and_then(|p| p.as_table_mut())
+    {
+        changes += order_package_keys(package, desired_order);
+    }
+
+    Ok(changes)
+}
+
+/// Put the keys of a package table in `desired_order`, with the keys
+/// that are not listed after them and `metadata` always last. Keys are
+/// compared in the order they are written, so a `[package.metadata.*]`
+/// table follows the `[package]` header.
+fn order_package_keys(package: &mut Table, desired_order: &[String]) -> usize {
+    let (entries, sub_tables) = written_keys(package);
+    let mut order: Vec<String> = desired_order
+        .iter()
+        .filter(|key| *key != "metadata")
+        .cloned()
+        .collect();
+    for key in entries.iter().chain(&sub_tables) {
+        if key != "metadata" && !order.contains(key) {
+            order.push(key.clone());
+        }
+    }
+    order.push("metadata".to_string());
+    reorder_table(
+        package,
+        &expected_order(&entries, &order),
+        &expected_order(&sub_tables, &order),
+    )
+}
+
+/// Put the keys of `[workspace]` in `desired_order`, comparing them in
+/// the order they are written. Sub-tables such as
+/// `[workspace.dependencies]` follow the `[workspace]` header.
+fn order_workspace_keys(workspace: &mut Table, desired_order: &[String]) -> usize {
+    let (entries, sub_tables) = written_keys(workspace);
+    reorder_table(
+        workspace,
+        &expected_order(&entries, desired_order),
+        &expected_order(&sub_tables, desired_order),
+    )
+}
+
+/// Put the keys of `table` in `desired_order`. Keys that are not listed
+/// follow in their original relative order.
+fn order_table_keys(table: &mut Table, desired_order: &[String]) -> usize {
+    let current_keys: Vec<String> = table.iter().map(|(k, _)| k.to_string()).collect();
+    let expected_keys = expected_order(&current_keys, desired_order);
+    if current_keys == expected_keys {
+        return 0;
+    }
+
+    // Need to reorder - collect all entries first
+    let mut entries = BTreeMap::new();
+    for key in &current_keys {
+        if let Some(entry) = table.remove_entry(key) {
+            entries.insert(key.clone(), entry);
+        }
+    }
+
+    // Re-insert in desired order, keeping each key's comments
+    for key in &expected_keys {
+        if let Some((key, item)) = entries.remove(key) {
+            table.insert_formatted(&key, item);
+        }
+    }
+
+    1
+}
+
+/// Put the keys of an inline table in `desired_order`, like
+/// [`order_table_keys`]. The spacing is normalized when keys move.
+fn order_inline_table_keys(table: &mut InlineTable, desired_order: &[String]) -> usize {
+    let current_keys: Vec<String> = table.iter().map(|(k, _)| k.to_string()).collect();
+    let expected_keys = expected_order(&current_keys, desired_order);
+    if current_keys == expected_keys {
+        return 0;
+    }
+
+    let mut entries = BTreeMap::new();
+    for key in &current_keys {
+        if let Some(entry) = table.remove_entry(key) {
+            entries.insert(key.clone(), entry);
+        }
+    }
+    for key in &expected_keys {
+        if let Some((key, value)) = entries.remove(key) {
+            table.insert_formatted(&key, value);
+        }
+    }
+    table.fmt();
+
+    1
+}
+
+/// The listed keys of `current_keys` in `desired_order`, followed by the
+/// others in their original relative order.
+fn expected_order(current_keys: &[String], desired_order: &[String]) -> Vec<String> {
+    let mut expected_keys = Vec::new();
+    for key in desired_order {
+        if current_keys.contains(key) {
+            expected_keys.push(key.clone());
+        }
+    }
+
+    // Add any keys that aren't in desired_order at the end
+    for key in current_keys {
+        if !desired_order.contains(key) {
+            expected_keys.push(key.clone());
+        }
+    }
+    expected_keys
+}
+
+/// Order the keys of every dependency spec in a dependency table.
+fn order_dependency_specs(deps: &mut Table, desired_order: &[String]) -> usize {
+    let mut changes = 0;
+    for (_name, item) in deps.iter_mut() {
+        match item {
+            Item::Table(spec) => changes += order_table_keys(spec, desired_order),
+            Item::Value(Value::InlineTable(spec)) => {
+                changes += order_inline_table_keys(spec, desired_order);
+            }
+            _ => {}
+        }
+    }
+    changes
+}
+
+/// Sort the entries of a dependency table. Key/value entries are always
+/// written before the `[section.name]` sub-tables, so the two are sorted
+/// separately and compared in the order they are written.
+fn sort_table_in_place(table: &mut Table, mode: DependencySort) -> Result<usize> {
+    let (entry_keys, table_keys) = written_keys(table);
+    let groups = match mode {
+        DependencySort::Alphabetical => vec![entry_keys.clone()],
+        DependencySort::Grouped => key_groups(table),
+    };
+
+    let mut sorted_keys = Vec::new();
+    for mut group in groups {
+        group.sort();
+        if mode == DependencySort::Grouped {
+            move_group_header(table, &entry_keys[sorted_keys.len()], &group[0]);
+        }
+        sorted_keys.extend(group);
+    }
+    let mut sorted_tables = table_keys;
+    sorted_tables.sort();
+
+    Ok(reorder_table(table, &sorted_keys, &sorted_tables))
+}
+
+/// Whether `item` is written under its own header, after the key/value
+/// entries of its parent.
+fn is_sub_table(item: &Item) -> bool {
+    match item {
+        Item::Table(table) => !table.is_dotted(),
+        Item::ArrayOfTables(_) => true,
+        _ => false,
+    }
+}
+
+/// The keys of a table's key/value entries and of its sub-tables, each
+/// in the order they are written. Like toml_edit, a sub-table without a
+/// position follows the one before it.
+fn written_keys(table: &Table) -> (Vec<String>, Vec<String>) {
+    let mut entries = Vec::new();
+    let mut sub_tables = Vec::new();
+    let mut last_position = table.position().unwrap_or(0);
+    for (key, item) in table.iter() {
+        if !is_sub_table(item) {
+            entries.push(key.to_string());
+            continue;
+        }
+        if let Some(position) = first_position(item) {
+            last_position = position;
+        }
+        sub_tables.push((last_position, key.to_string()));
+    }
+    sub_tables.sort_by_key(|(position, _)| *position);
+    (
+        entries,
+        sub_tables.into_iter().map(|(_, key)| key).collect(),
+    )
+}
+
+/// Order the `[target.<cfg>]` tables by their cfg expression or target
+/// triple.
+fn sort_targets(doc: &mut DocumentMut) -> usize {
+    let Some(targets) = doc.get_mut("target").and_then(|t| t.as_table_mut()) else {
+        return 0;
+    };
+    let (mut entries, mut sub_tables) = written_keys(targets);
+    entries.sort();
+    sub_tables.sort();
+    reorder_table(targets, &entries, &sub_tables)
+}
+
+/// The position of the first header written for `item`: its own, or
+/// for an implicit table such as `package.metadata` the first of its
+/// sub-tables.
+fn first_position(item: &Item) -> Option<isize> {
+    match item {
+        Item::Table(table) => table.position().or_else(|| {
+            table
+                .iter()
+                .filter_map(|(_, inner)| first_position(inner))
+                .min()
+        }),
+        Item::ArrayOfTables(array) => array.iter().filter_map(Table::position).min(),
+        _ => None,
+    }
+}
+
+/// Put the key/value entries and the sub-tables of `table` in the given
+/// orders, each a permutation of what [`written_keys`] returns, and
+/// return 1 when that changes what is written. Sub-tables render by
+/// their position in the document, so the positions that the table and
+/// its sub-tables occupy are handed out again: the table's own header
+/// first, then its sub-tables in the new order.
+fn reorder_table(table: &mut Table, entries: &[String], sub_tables: &[String]) -> usize {
+    let (current_entries, current_tables) = written_keys(table);
+    let header_first = table.position().is_none_or(|own| {
+        table
+            .iter()
+            .filter_map(|(_, item)| first_position(item))
+            .all(|position| position > own)
+    });
+    if header_first && current_entries == entries && current_tables == sub_tables {
+        return 0;
+    }
+
+    let mut positions = Vec::new();
+    positions.extend(take_position(table));
+    table_positions(table, &mut positions);
+    positions.sort_by_key(|(position, _)| *position);
+    let mut positions = positions.into_iter();
+    if table.position().is_some()
+        && let Some(own) = positions.next()
+    {
+        give_position(table, own);
+    }
+
+    // Entries are moved together with their keys so that leading
+    // comments (in the key decor) and trailing comments (in the value
+    // decor) stay with the entry they belong to.
+    let current_keys: Vec<String> = table.iter().map(|(k, _)| k.to_string()).collect();
+    let mut removed = BTreeMap::new();
+    for key in &current_keys {
+        if let Some(entry) = table.remove_entry(key) {
+            removed.insert(key.clone(), entry);
+        }
+    }
+    for key in entries.iter().chain(sub_tables) {
+        if let Some((key, item)) = removed.remove(key) {
+            table.insert_formatted(&key, item);
+        }
+    }
+
+    assign_positions(table, &mut positions);
+    1
+}
+
+/// Take the document position of `table`'s header together with the
+/// blank lines in front of it, which belong to the place in the
+/// document rather than to the table.
+fn take_position(table: &mut Table) -> Option<(isize, String)> {
+    let position = table.position()?;
+    let prefix = table
+        .decor()
+        .prefix()
+        .and_then(|p| p.as_str())
+        .unwrap_or("")
+        .to_string();
+    let (blank, rest) = prefix.split_at(leading_blank_len(&prefix));
+    let blank = blank.to_string();
+    table.decor_mut().set_prefix(rest.to_string());
+    Some((position, blank))
+}
+
+/// Put `table`'s header at a position taken with [`take_position`].
+fn give_position(table: &mut Table, (position, blank): (isize, String)) {
+    let rest = table
+        .decor()
+        .prefix()
+        .and_then(|p| p.as_str())
+        .unwrap_or("")
+        .to_string();
+    table.set_position(Some(position));
+    table.decor_mut().set_prefix(format!("{blank}{rest}"));
+}
+
+/// Collect the document positions of the tables nested in `table`,
+/// together with the blank lines in front of their headers. Both belong
+/// to the place in the document rather than to the table.
+fn table_positions(table: &mut Table, positions: &mut Vec<(isize, String)>) {
+    for (_, item) in table.iter_mut() {
+        if let Some(inner) = item.as_table_mut() {
+            positions.extend(take_position(inner));
+            table_positions(inner, positions);
+        }
+    }
+}
+
+/// Give the tables nested in `table` the next positions, in order.
+fn assign_positions(table: &mut Table, positions: &mut impl Iterator<Item = (isize, String)>) {
+    for (_, item) in table.iter_mut() {
+        if let Some(inner) = item.as_table_mut() {
+            if inner.position().is_some()
+                && let Some(next) = positions.next()
+            {
+                give_position(inner, next);
+            }
+            assign_positions(inner, positions);
+        }
+    }
+}
+
+/// Length of the whitespace-only lines at the start of some decor.
+fn leading_blank_len(prefix: &str) -> usize {
+    let mut len = 0;
+    for line in prefix.split_inclusive('\n') {
+        if !line.trim().is_empty() || !line.ends_with('\n') {
+            break;
+        }
+        len += line.len();
+    }
+    len
+}
+
+/// Split the key/value entries of a table into groups separated by
+/// blank lines.
+fn key_groups(table: &Table) -> Vec<Vec<String>> {
+    let mut groups: Vec<Vec<String>> = Vec::new();
+    for (key, _) in table.iter().filter(|(_, item)| !is_sub_table(item)) {
+        let prefix = leading_decor(table, key);
+        match groups.last_mut() {
+            Some(group) if !starts_group(&prefix) => group.push(key.to_string()),
+            _ => groups.push(vec![key.to_string()]),
+        }
+    }
+    groups
+}
+
+/// Whether the leading decor of a key holds a blank line.
+fn starts_group(prefix: &str) -> bool {
+    let mut lines: Vec<&str> = prefix.split('\n').collect();
+    // The last piece is the indentation of the key itself.
+    lines.pop();
+    lines.iter().any(|line| line.trim().is_empty())
+}
+
+/// Keep the header of a group (the blank line and comments above its
+/// first entry) at the top of the group when another entry sorts first.
+/// Comments of the entry that becomes first follow the header.
+fn move_group_header(table: &mut Table, old_first: &str, new_first: &str) {
+    if old_first == new_first {
+        return;
+    }
+    let header = leading_decor(table, old_first);
+    let own = leading_decor(table, new_first);
+    let Some(mut old_key) = leading_key_mut(table, old_first) else {
+        return;
+    };
+    old_key.leaf_decor_mut().set_prefix("");
+
+    let Some(mut new_key) = leading_key_mut(table, new_first) else {
+        return;
+    };
+    new_key
+        .leaf_decor_mut()
+        .set_prefix(format!("{header}{own}"));
+}
+
+/// The blank lines and comments above the entry `key` of `table`.
+fn leading_decor(table: &Table, key: &str) -> String {
+    match table.get(key) {
+        // The leading decor of a dotted entry such as
+        // `serde.workspace = true` sits in front of its first key.
+        Some(Item::Table(inner)) if inner.is_dotted() => inner
+            .iter()
+            .next()
+            .map(|(first, _)| leading_decor(inner, first))
+            .unwrap_or_default(),
+        _ => table
+            .key(key)
+            .and_then(|k| k.leaf_decor().prefix())
+            .and_then(|p| p.as_str())
+            .unwrap_or("")
+            .to_string(),
+    }
+}
+
+/// The key that holds the leading decor of the entry `key` of `table`,
+/// as read by [`leading_decor`].
+fn leading_key_mut<'a>(table: &'a mut Table, key: &str) -> Option<KeyMut<'a>> {
+    if !table
+        .get(key)
+        .is_some_and(|item| matches!(item, Item::Table(inner) if inner.is_dotted()))
+    {
+        return table.key_mut(key);
+    }
+    let inner = table.get_mut(key)?.as_table_mut()?;
+    let first = inner.iter().next()?.0.to_string();
+    leading_key_mut(inner, &first)
+}
+
+/// Sort the `features` array of every dependency spec in a dependency
+/// table.
+fn sort_spec_features(deps: &mut Table) -> usize {
+    let mut changes = 0;
+    for (_name, item) in deps.iter_mut() {
+        let features = match item {
+            Item::Table(spec) => spec.get_mut("features").and_then(|f| f.as_array_mut()),
+            Item::Value(Value::InlineTable(spec)) => {
+                spec.get_mut("features").and_then(|f| f.as_array_mut())
+            }
+            _ => None,
+        };
+        if let Some(features) = features {
+            changes += sort_feature_entries(features);
+        }
+    }
+    changes
+}
+
+/// Deduplicate the entries of a feature and sort them: local features
+/// first, then `dep:` entries, then `crate/feature` entries, each
+/// alphabetically. Entries move together with their comments. The
+/// features enabled on a dependency are all plain names, so they simply
+/// end up in alphabetical order.
+fn sort_feature_entries(entries: &mut Array) -> usize {
+    sort_array_entries(entries, feature_entry_order)
+}
+
+/// Deduplicate the entries of an array and sort them by `order`.
+/// Entries move together with their comments; a multi-line array stays
+/// multi-line.
+fn sort_array_entries<K: Ord>(entries: &mut Array, order: impl Fn(&Value) -> K) -> usize {
+    let current: Vec<String> = entries.iter().map(|v| v.to_string()).collect();
+    let mut values: Vec<Value> = entries.iter().cloned().collect();
+    values.sort_by_key(&order);
+    values.dedup_by_key(|value| order(value));
+
+    let sorted: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+    if current == sorted {
+        return 0;
+    }
+
+    let multi_line = current.iter().any(|entry| entry.contains('\n'));
+    let trailing_comma = entries.trailing_comma();
+    let trailing = entries.trailing().clone();
+    entries.clear();
+    for value in values {
+        entries.push_formatted(value);
+    }
+    if multi_line {
+        entries.set_trailing_comma(trailing_comma);
+        entries.set_trailing(trailing);
+    } else {
+        entries.fmt();
+    }
+    1
+}
+
+/// Sort key of a feature entry: its kind, then its name.
+fn feature_entry_order(value: &Value) -> (u8, String) {
+    let entry = value.as_str().unwrap_or_default().to_string();
+    let kind = if entry.starts_with("dep:") {
+        1
+    } else if entry.contains('/') {
+        2
+    } else {
+        0
+    };
+    (kind, entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Helper that runs `reorder_sections` on the given TOML string
+    /// and returns the resulting TOML string.
+    fn reorder(input: &str) -> String {
+        let mut doc = input.parse::<DocumentMut>().expect("valid TOML");
+        let config = Config::default();
+        reorder_sections(&mut doc, &config.section_order).expect("reorder succeeded");
+        doc.to_string()
+    }
+
+    #[test]
+    fn workspace_dotted_sections_preserved() {
+        let input = "\
+[package]
+name = \"test-workspace\"
+version = \"0.0.0\"
+
+[workspace]
+members = [\"crate-a\"]
+resolver = \"3\"
+
+[profile]
+
+[workspace.package]
+rust-version = \"1.93.0\"
+edition = \"2024\"
+
+[workspace.dependencies]
+serde = { version = \"1.0\", features = [\"derive\"] }
+tokio = { version = \"1.0\" }
+";
+        let result = reorder(input);
+
+        // All dotted workspace sections must be present
+        assert!(
+            result.contains("[workspace.package]"),
+            "missing [workspace.package] in:\n{result}"
+        );
+        assert!(
+            result.contains("[workspace.dependencies]"),
+            "missing [workspace.dependencies] in:\n{result}"
+        );
+        assert!(
+            result.contains("rust-version"),
+            "missing rust-version field in:\n{result}"
+        );
+        assert!(
+            result.contains("serde"),
+            "missing serde dependency in:\n{result}"
+        );
+        assert!(
+            result.contains("tokio"),
+            "missing tokio dependency in:\n{result}"
+        );
+        assert!(
+            result.contains("[profile]"),
+            "missing [profile] in:\n{result}"
+        );
+    }
+
+    #[test]
+    fn sections_not_in_order_list_are_preserved() {
+        let input = "\
+[package]
+name = \"test\"
+
+[lints]
+workspace = true
+
+[dependencies]
+serde = \"1.0\"
+";
+        let result = reorder(input);
+
+        assert!(
+            result.contains("[lints]"),
+            "missing [lints] section in:\n{result}"
+        );
+        assert!(
+            result.contains("workspace = true"),
+            "missing lints content in:\n{result}"
+        );
+    }
+
+    #[test]
+    fn no_truncation_with_many_dotted_sections() {
+        let input = "\
+[package]
+name = \"big-workspace\"
+version = \"0.0.0\"
+
+[workspace]
+members = [\"a\", \"b\", \"c\"]
+resolver = \"3\"
+
+[profile.release]
+opt-level = 3
+
+[profile.dev]
+opt-level = 0
+
+[workspace.package]
+edition = \"2024\"
+license = \"MIT\"
+
+[workspace.dependencies]
+anyhow = \"1.0\"
+clap = { version = \"4.0\", features = [\"derive\"] }
+serde = { version = \"1.0\" }
+tokio = { version = \"1.0\" }
+";
+        let result = reorder(input);
+
+        // Verify nothing is lost
+        assert!(
+            result.contains("[workspace.package]"),
+            "missing [workspace.package]:\n{result}"
+        );
+        assert!(
+            result.contains("[workspace.dependencies]"),
+            "missing [workspace.dependencies]:\n{result}"
+        );
+        assert!(
+            result.contains("[profile.release]"),
+            "missing [profile.release]:\n{result}"
+        );
+        assert!(
+            result.contains("[profile.dev]"),
+            "missing [profile.dev]:\n{result}"
+        );
+        assert!(result.contains("anyhow"), "missing anyhow dep:\n{result}");
+        assert!(result.contains("tokio"), "missing tokio dep:\n{result}");
+        assert!(
+            result.contains("edition = \"2024\""),
+            "missing edition field:\n{result}"
+        );
+    }
+
+    #[test]
+    fn lints_clippy_with_inline_priority_preserved() {
+        // Reproduces the reported bug: a [lints.clippy] section with
+        // entries like `disallowed_types = { level = "warn", priority = 1 }`
+        // was causing "Failed to parse reordered document" errors.
+        // The line-based parser must not misidentify value lines
+        // containing brackets as section headers.
+        let input = "\
+[lints.clippy]
+disallowed_types = { level = \"warn\", priority = 1 }
+disallowed-names = { level = \"warn\", priority = -1 }
+
+[package]
+name = \"test-crate\"
+version = \"0.1.0\"
+
+[dependencies]
+serde = \"1.0\"
+";
+        let result = reorder(input);
+
+        assert!(
+            result.contains("[lints.clippy]"),
+            "missing [lints.clippy] in:\n{result}"
+        );
+        assert!(
+            result.contains("priority = 1"),
+            "missing priority = 1 in:\n{result}"
+        );
+        assert!(
+            result.contains("priority = -1"),
+            "missing priority = -1 in:\n{result}"
+        );
+        assert!(
+            result.contains("[package]"),
+            "missing [package] in:\n{result}"
+        );
+        assert!(
+            result.contains("[dependencies]"),
+            "missing [dependencies] in:\n{result}"
+        );
+    }
+
+    #[test]
+    fn multiline_arrays_not_misidentified_as_headers() {
+        // Value lines starting with [ (array elements, nested arrays)
+        // must not be misidentified as section headers.
+        let input = "\
+[package]
+name = \"test\"
+categories = [
+    \"command-line-utilities\",
+    \"development-tools\",
+]
+
+[features]
+default = [\"std\"]
+
+[dependencies]
+serde = \"1.0\"
+";
+        let result = reorder(input);
+
+        assert!(
+            result.contains("categories"),
+            "missing categories in:\n{result}"
+        );
+        assert!(
+            result.contains("command-line-utilities"),
+            "missing array element in:\n{result}"
+        );
+        assert!(
+            result.contains("[features]"),
+            "missing [features] in:\n{result}"
+        );
+    }
+
+    #[test]
+    fn nested_array_values_not_misidentified_as_headers() {
+        // Nested arrays like [[1, 2], [3, 4]] should not be treated
+        // as [[array-of-tables]] headers.
+        let input = "\
+[package]
+name = \"test\"
+
+[metadata]
+matrix = [
+    [1, 2],
+    [3, 4],
+]
+
+[dependencies]
+serde = \"1.0\"
+";
+        let result = reorder(input);
+
+        assert!(
+            result.contains("[metadata]"),
+            "missing [metadata] in:\n{result}"
+        );
+        assert!(
+            result.contains("[1, 2]"),
+            "missing nested array [1, 2] in:\n{result}"
+        );
+        assert!(
+            result.contains("[3, 4]"),
+            "missing nested array [3, 4] in:\n{result}"
+        );
+    }
+
+    #[test]
+    fn multiline_feature_arrays_with_brackets() {
+        // Feature arrays with entries in brackets on their own line
+        // must not be misidentified as section headers. This
+        // reproduces the reported "invalid multi-line basic string"
+        // error when inline tables get expanded to multi-line.
+        let input = "\
+[package]
+name = \"test\"
+keywords = [
+    \"cargo\",
+    \"toml\",
+]
+
+[features]
+full = [
+    \"derive\",
+    \"std\",
+]
+
+[dependencies]
+serde = \"1.0\"
+";
+        let result = reorder(input);
+
+        assert!(
+            result.contains("[features]"),
+            "missing [features] in:\n{result}"
+        );
+        assert!(
+            result.contains("\"derive\""),
+            "missing derive feature in:\n{result}"
+        );
+        assert!(
+            result.contains("keywords"),
+            "missing keywords in:\n{result}"
+        );
+    }
+
+    /// Helper that runs the full formatting pipeline on a TOML string
+    /// (collapse + reorder + format_package + sort) and returns the
+    /// result.
+    fn full_format(input: &str) -> String {
+        let mut doc = input.parse::<DocumentMut>().expect("valid TOML");
+        crate::format_document(&mut doc, &Config::default()).expect("format succeeded");
+        doc.to_string()
+    }
+
+    #[test]
+    fn full_pipeline_workspace_lints_with_comments() {
+        // Reproduces the reported bug: a workspace Cargo.toml with
+        // [workspace.lints.clippy] entries containing trailing
+        // comments after quoted string values was causing parse
+        // errors during reordering.
+        let input = "\
+[package]
+name = \"my-workspace\"
+version = \"0.0.0\"
+publish = false
+
+[workspace]
+members = [\"crate-a\", \"crate-b\"]
+resolver = \"3\"
+
+[workspace.lints.clippy]
+missing_crate_level_docs = \"deny\" # require crate-level docs
+disallowed_types = { level = \"warn\", priority = 1 }
+
+[workspace.lints.rust]
+missing_docs = \"warn\"
+unsafe_code = \"forbid\" # never allow unsafe
+
+[workspace.package]
+rust-version = \"1.93.0\"
+edition = \"2024\"
+license = \"Apache-2.0\"
+
+[workspace.dependencies]
+serde = { version = \"1.0\", features = [\"derive\"] }
+tokio = { version = \"1.0\", features = [\"full\"] }
+anyhow = \"1.0\"
+
+[profile.release]
+opt-level = 3
+";
+        let result = full_format(input);
+
+        // Verify all sections are preserved
+        assert!(
+            result.contains("[workspace.lints.clippy]"),
+            "missing [workspace.lints.clippy] in:\n{result}"
+        );
+        assert!(
+            result.contains("[workspace.lints.rust]"),
+            "missing [workspace.lints.rust] in:\n{result}"
+        );
+        assert!(
+            result.contains("[workspace.package]"),
+            "missing [workspace.package] in:\n{result}"
+        );
+        assert!(
+            result.contains("[workspace.dependencies]"),
+            "missing [workspace.dependencies] in:\n{result}"
+        );
+        assert!(
+            result.contains("[profile.release]"),
+            "missing [profile.release] in:\n{result}"
+        );
+        // Verify comments are preserved
+        assert!(
+            result.contains("# require crate-level docs"),
+            "missing trailing comment in:\n{result}"
+        );
+        assert!(
+            result.contains("# never allow unsafe"),
+            "missing trailing comment in:\n{result}"
+        );
+        // Verify values are preserved
+        assert!(
+            result.contains("missing_crate_level_docs"),
+            "missing lint entry in:\n{result}"
+        );
+        assert!(
+            result.contains("priority = 1"),
+            "missing priority in:\n{result}"
+        );
+    }
+
+    #[test]
+    fn full_pipeline_lints_out_of_order() {
+        // When [lints.clippy] appears before [package], the tool
+        // must reorder correctly without corrupting values.
+        let input = "\
+[lints.clippy]
+needless_pass_by_value = \"warn\"
+missing_errors_doc = \"warn\"
+
+[lints.rust]
+unsafe_code = \"forbid\"
+
+[package]
+name = \"test-crate\"
+version = \"0.1.0\"
+edition = \"2024\"
+
+[dependencies]
+serde = { version = \"1.0\", features = [\"derive\"] }
+tokio = \"1.0\"
+anyhow = \"1.0\"
+";
+        let result = full_format(input);
+
+        // [package] should come before [dependencies]
+        let pkg_pos = result.find("[package]").expect("missing [package]");
+        let dep_pos = result
+            .find("[dependencies]")
+            .expect("missing [dependencies]");
+        assert!(
+            pkg_pos < dep_pos,
+            "[package] should come before [dependencies]"
+        );
+        // lints should still be present
+        assert!(
+            result.contains("[lints.clippy]"),
+            "missing [lints.clippy] in:\n{result}"
+        );
+        assert!(
+            result.contains("[lints.rust]"),
+            "missing [lints.rust] in:\n{result}"
+        );
+        assert!(
+            result.contains("needless_pass_by_value"),
+            "missing lint entry in:\n{result}"
+        );
+        // dependencies should be sorted
+        let anyhow_pos = result.find("anyhow").expect("missing anyhow");
+        let serde_pos = result.find("serde").expect("missing serde");
+        let tokio_pos = result.find("tokio").expect("missing tokio");
+        assert!(
+            anyhow_pos < serde_pos && serde_pos < tokio_pos,
+            "dependencies should be sorted alphabetically"
+        );
+    }
+
+    #[test]
+    fn full_pipeline_workspace_lints_explicit_tables() {
+        // Test with [workspace.lints.clippy.disallowed-names] as an
+        // explicit sub-table (not inline) — this is how toml_edit
+        // may serialize certain lint configurations.
+        let input = "\
+[workspace]
+members = [\"crate-a\"]
+resolver = \"3\"
+
+[workspace.lints.clippy]
+needless_pass_by_value = \"warn\"
+
+[workspace.lints.clippy.disallowed-names]
+level = \"warn\"
+priority = -1
+
+[workspace.lints.clippy.disallowed_types]
+level = \"warn\"
+priority = 1
+
+[workspace.lints.rust]
+missing_docs = \"warn\"
+
+[workspace.package]
+edition = \"2024\"
+
+[package]
+name = \"my-workspace\"
+version = \"0.0.0\"
+
+[dependencies]
+serde = \"1.0\"
+";
+        let result = full_format(input);
+
+        assert!(
+            result.contains("disallowed-names"),
+            "missing disallowed-names in:\n{result}"
+        );
+        assert!(
+            result.contains("disallowed_types"),
+            "missing disallowed_types in:\n{result}"
+        );
+        assert!(
+            result.contains("priority = -1"),
+            "missing priority = -1 in:\n{result}"
+        );
+        assert!(
+            result.contains("priority = 1"),
+            "missing priority = 1 in:\n{result}"
+        );
+        assert!(
+            result.contains("[workspace.package]"),
+            "missing [workspace.package] in:\n{result}"
+        );
+    }
+
+    #[test]
+    fn reorder_preserves_non_contiguous_dotted_sections() {
+        // When [workspace] appears early and [workspace.package]
+        // appears much later (separated by non-workspace sections),
+        // both must be grouped together in the output.
+        let input = "\
+[package]
+name = \"test\"
+version = \"0.0.0\"
+
+[dependencies]
+serde = \"1.0\"
+
+[workspace]
+members = [\"a\"]
+
+[features]
+default = []
+
+[workspace.package]
+edition = \"2024\"
+
+[workspace.dependencies]
+anyhow = \"1.0\"
+";
+        let result = reorder(input);
+
+        assert!(
+            result.contains("[workspace.package]"),
+            "missing [workspace.package] in:\n{result}"
+        );
+        assert!(
+            result.contains("[workspace.dependencies]"),
+            "missing [workspace.dependencies] in:\n{result}"
+        );
+        assert!(
+            result.contains("edition = \"2024\""),
+            "missing edition in:\n{result}"
+        );
+    }
+
+    #[test]
+    fn non_contiguous_workspace_sections_across_profile() {
+        // Mimics the reported scenario: [workspace] at the top,
+        // [profile] in the middle, then [workspace.package] and
+        // [workspace.lints.*] and [workspace.dependencies] after.
+        // The parser must group all workspace.* sections with
+        // [workspace] even when [profile] separates them.
+        let input = "\
+[package]
+name = \"my-workspace\"
+version = \"0.0.0\"
+publish = false
+
+[workspace]
+members = [
+    \"crate-a\",
+    \"crate-b\",
+]
+resolver = \"3\"
+
+[profile]
+
+[workspace.package]
+rust-version = \"1.93.0\"
+edition = \"2024\"
+license = \"Apache-2.0\"
+authors = [\"Test Author <test@example.com>\"]
+
+[workspace.lints.clippy]
+missing_errors_doc = \"warn\"
+needless_pass_by_value = \"warn\"
+disallowed_types = { level = \"warn\", priority = 1 }
+
+[workspace.lints.rust]
+missing_docs = \"warn\"
+unsafe_code = \"forbid\"
+
+[workspace.dependencies]
+anyhow = \"1.0\"
+clap = { version = \"4.0\", features = [\"derive\"] }
+serde = { version = \"1.0\", features = [\"derive\"] }
+tokio = { version = \"1.0\", features = [\"full\"] }
+tracing = \"0.1\"
+";
+        let result = full_format(input);
+
+        // All workspace sub-sections must be present
+        assert!(
+            result.contains("[workspace.package]"),
+            "missing [workspace.package] in:\n{result}"
+        );
+        assert!(
+            result.contains("[workspace.lints.clippy]"),
+            "missing [workspace.lints.clippy] in:\n{result}"
+        );
+        assert!(
+            result.contains("[workspace.lints.rust]"),
+            "missing [workspace.lints.rust] in:\n{result}"
+        );
+        assert!(
+            result.contains("[workspace.dependencies]"),
+            "missing [workspace.dependencies] in:\n{result}"
+        );
+        assert!(
+            result.contains("[profile]"),
+            "missing [profile] in:\n{result}"
+        );
+        // Verify content
+        assert!(
+            result.contains("rust-version"),
+            "missing rust-version in:\n{result}"
+        );
+        assert!(
+            result.contains("disallowed_types"),
+            "missing disallowed_types in:\n{result}"
+        );
+        assert!(
+            result.contains("tracing"),
+            "missing tracing dep in:\n{result}"
+        );
+    }
+
+    #[test]
+    fn real_workspace_with_profile_subsections_and_lints() {
+        // Reproduces exact structure from bug report: [profile]
+        // with multiple sub-profiles, followed by comment block,
+        // then [workspace.lints.*] sections.
+        let input = "\
+########################################
+# Virtual workspace root
+########################################
+[workspace]
+members = [
+    \"crate-a\",
+    \"crate-b\",
+]
+resolver = \"3\"
+
+[package]
+name = \"my-workspace\"
+version = \"0.0.0\"
+edition = \"2024\"
+publish = false
+
+[build-dependencies]
+rhusky = \"0.0.2\"
+
+[workspace.package]
+edition = \"2024\"
+version = \"0.0.0\" # Version dynamically managed by CI
+license-file = \"LICENSE\"
+rust-version = \"1.93.0\"
+
+[workspace.dependencies]
+anyhow = \"1.0\"
+serde = { version = \"1.0\", features = [\"derive\"] }
+tokio = { version = \"1.0\", features = [\"full\"] }
+
+[profile]
+
+[profile.wasm-dev]
+inherits = \"dev\"
+opt-level = 1
+
+[profile.release]
+debug = false
+strip = \"debuginfo\"
+
+# Workspace-wide lint levels
+[workspace.lints.rust]
+warnings = \"deny\"     # never allow warnings to pass
+missing_docs = \"deny\" # require docs on all public items
+
+[workspace.lints.rustdoc]
+missing_crate_level_docs = \"deny\" # require crate-level docs
+broken_intra_doc_links = \"deny\"   # enforce valid intra-doc links
+bare_urls = \"warn\"                # prefer backticks or proper links
+
+[workspace.lints.clippy]
+missing_panics_doc = \"warn\"                         # document panics
+missing_errors_doc = \"warn\"                         # document errors
+doc_markdown = \"warn\"                               # backticks for code
+disallowed_types = { level = \"warn\", priority = 1 }
+
+[workspace.metadata.clippy]
+disallowed-types = [\"serde_json::Value\"]
+
+########################################
+# Patches for dependencies
+########################################
+[patch.crates-io]
+# No patches currently needed
+";
+        let result = full_format(input);
+
+        // All sections must survive
+        assert!(
+            result.contains("[workspace.lints.rust]"),
+            "missing [workspace.lints.rust] in:\n{result}"
+        );
+        assert!(
+            result.contains("[workspace.lints.rustdoc]"),
+            "missing [workspace.lints.rustdoc] in:\n{result}"
+        );
+        assert!(
+            result.contains("[workspace.lints.clippy]"),
+            "missing [workspace.lints.clippy] in:\n{result}"
+        );
+        assert!(
+            result.contains("[workspace.metadata.clippy]"),
+            "missing [workspace.metadata.clippy] in:\n{result}"
+        );
+        assert!(
+            result.contains("[patch.crates-io]"),
+            "missing [patch.crates-io] in:\n{result}"
+        );
+        assert!(
+            result.contains("# never allow warnings to pass"),
+            "missing trailing comment in:\n{result}"
+        );
+        // Verify output is valid TOML
+        let reparsed = result.parse::<DocumentMut>();
+        assert!(
+            reparsed.is_ok(),
+            "Output is not valid TOML:\n{result}\nError: {}",
+            reparsed.unwrap_err()
+        );
+    }
+
+    #[test]
+    fn reorder_actual_test_file() {
+        // Test with the actual file content from /tmp that triggers
+        // the parse error.
+        let input =
+            std::fs::read_to_string("/tmp/cargo-fmt-toml-test-case.toml").unwrap_or_default();
+        if input.is_empty() {
+            // Skip if the test file doesn't exist
+            return;
+        }
+        let result = full_format(&input);
+
+        // Verify the output is valid TOML
+        let reparsed = result.parse::<DocumentMut>();
+        assert!(
+            reparsed.is_ok(),
+            "Output is not valid TOML:\n{result}\nError: {}",
+            reparsed.unwrap_err()
+        );
+    }
+
+    #[test]
+    fn full_pipeline_output_is_valid_toml() {
+        // Verify the full pipeline produces valid TOML that can be
+        // parsed back without errors.
+        let input = "\
+[package]
+name = \"test-workspace\"
+version = \"0.0.0\"
+publish = false
+
+[workspace]
+members = [
+    \"crate-a\",
+    \"crate-b\",
+]
+resolver = \"3\"
+
+[profile]
+
+[workspace.package]
+rust-version = \"1.93.0\"
+edition = \"2024\"
+license = \"Apache-2.0\"
+
+[workspace.lints.clippy]
+missing_errors_doc = \"warn\"
+missing_crate_level_docs = \"deny\" # require crate-level docs
+disallowed_types = { level = \"warn\", priority = 1 }
+
+[workspace.lints.rust]
+missing_docs = \"warn\"
+unsafe_code = \"forbid\" # never allow unsafe
+
+[workspace.dependencies]
+serde = { version = \"1.0\", features = [\"derive\"] }
+tokio = { version = \"1.0\" }
+anyhow = \"1.0\"
+";
+        // Run the full pipeline
+        let result = full_format(input);
+
+        // Verify the output is valid TOML
+        let reparsed = result.parse::<DocumentMut>();
+        assert!(
+            reparsed.is_ok(),
+            "Output is not valid TOML:\n{result}\nError: {}",
+            reparsed.unwrap_err()
+        );
+    }
+
+    #[test]
+    fn full_pipeline_is_idempotent() {
+        // Running the formatter twice must produce the same output.
+        let input = "\
+[workspace]
+members = [\"crate-a\"]
+resolver = \"3\"
+
+[package]
+name = \"test\"
+version = \"0.0.0\"
+
+[workspace.lints.clippy]
+missing_errors_doc = \"warn\"
+disallowed_types = { level = \"warn\", priority = 1 }
+
+[workspace.package]
+edition = \"2024\"
+rust-version = \"1.93.0\"
+
+[dependencies]
+tokio = \"1.0\"
+anyhow = \"1.0\"
+serde = \"1.0\"
+
+[workspace.dependencies]
+serde = { version = \"1.0\", features = [\"derive\"] }
+";
+        let first = full_format(input);
+        let second = full_format(&first);
+        assert_eq!(
+            first, second,
+            "Formatter is not idempotent.\nFirst:\n{first}\nSecond:\n{second}"
+        );
+    }
+
+    #[test]
+    fn array_of_tables_preserved() {
+        // [[bin]] and [[example]] are array-of-tables headers that
+        // must be preserved and reordered with their parent key.
+        let input = "\
+[dependencies]
+serde = \"1.0\"
+
+[[bin]]
+name = \"my-tool\"
+path = \"src/main.rs\"
+
+[[bin]]
+name = \"helper\"
+path = \"src/helper.rs\"
+
+[package]
+name = \"test\"
+version = \"0.1.0\"
+";
+        let result = full_format(input);
+
+        // [package] should come before [[bin]] and [dependencies]
+        let pkg_pos = result.find("[package]").expect("missing [package]");
+        let bin_pos = result
+            .find("[[bin]]")
+            .unwrap_or_else(|| panic!("missing [[bin]] in:\n{result}"));
+        let dep_pos = result
+            .find("[dependencies]")
+            .expect("missing [dependencies]");
+        assert!(
+            pkg_pos < bin_pos,
+            "[package] should come before [[bin]] in:\n{result}"
+        );
+        assert!(
+            bin_pos < dep_pos,
+            "[[bin]] should come before [dependencies] in:\n{result}"
+        );
+        // Both [[bin]] entries must survive
+        let bin_count = result.matches("[[bin]]").count();
+        assert_eq!(bin_count, 2, "expected 2 [[bin]] entries, got {bin_count}");
+        assert!(result.contains("my-tool"), "missing my-tool in:\n{result}");
+        assert!(result.contains("helper"), "missing helper in:\n{result}");
+        // Output must be valid TOML
+        let reparsed = result.parse::<DocumentMut>();
+        assert!(
+            reparsed.is_ok(),
+            "Output is not valid TOML:\n{result}\nError: {}",
+            reparsed.unwrap_err()
+        );
+    }
+
+    #[test]
+    fn all_reorder_tests_produce_valid_toml() {
+        // Verify every test scenario produces valid TOML output,
+        // not just that expected strings are present.
+        let inputs = [
+            // workspace_dotted_sections_preserved
+            "\
+[package]
+name = \"test-workspace\"
+version = \"0.0.0\"
+
+[workspace]
+members = [\"crate-a\"]
+resolver = \"3\"
+
+[profile]
+
+[workspace.package]
+rust-version = \"1.93.0\"
+edition = \"2024\"
+
+[workspace.dependencies]
+serde = { version = \"1.0\", features = [\"derive\"] }
+tokio = { version = \"1.0\" }
+",
+            // sections_not_in_order_list_are_preserved
+            "\
+[package]
+name = \"test\"
+
+[lints]
+workspace = true
+
+[dependencies]
+serde = \"1.0\"
+",
+            // lints_clippy_with_inline_priority_preserved
+            "\
+[lints.clippy]
+disallowed_types = { level = \"warn\", priority = 1 }
+disallowed-names = { level = \"warn\", priority = -1 }
+
+[package]
+name = \"test-crate\"
+version = \"0.1.0\"
+
+[dependencies]
+serde = \"1.0\"
+",
+            // non_contiguous_workspace_sections_across_profile
+            "\
+[package]
+name = \"my-workspace\"
+version = \"0.0.0\"
+publish = false
+
+[workspace]
+members = [
+    \"crate-a\",
+    \"crate-b\",
+]
+resolver = \"3\"
+
+[profile]
+
+[workspace.package]
+rust-version = \"1.93.0\"
+edition = \"2024\"
+license = \"Apache-2.0\"
+authors = [\"Test Author <test@example.com>\"]
+
+[workspace.lints.clippy]
+missing_errors_doc = \"warn\"
+needless_pass_by_value = \"warn\"
+disallowed_types = { level = \"warn\", priority = 1 }
+
+[workspace.lints.rust]
+missing_docs = \"warn\"
+unsafe_code = \"forbid\"
+
+[workspace.dependencies]
+anyhow = \"1.0\"
+clap = { version = \"4.0\", features = [\"derive\"] }
+serde = { version = \"1.0\", features = [\"derive\"] }
+tokio = { version = \"1.0\", features = [\"full\"] }
+tracing = \"0.1\"
+",
+        ];
+
+        for (idx, input) in inputs.iter().enumerate() {
+            let result = full_format(input);
+            let reparsed = result.parse::<DocumentMut>();
+            assert!(
+                reparsed.is_ok(),
+                "Scenario {idx} produced invalid TOML:\n{result}\nError: {}",
+                reparsed.unwrap_err()
+            );
+        }
+    }
+
+    #[test]
+    fn rule_ids_are_known_to_the_configuration() {
+        let ids: Vec<&str> = all().iter().map(|rule| rule.id()).collect();
+        for id in &ids {
+            assert!(crate::config::RULE_IDS.contains(id), "unknown rule id {id}");
+        }
+        assert_eq!(
+            ids,
+            [
+                "simplify-dependency-specs",
+                "collapse-nested-tables",
+                "expand-inline-tables",
+                "inheritance-style",
+                "reorder-sections",
+                "package-order",
+                "workspace-order",
+                "dependency-key-order",
+                "sort-dependencies",
+                "sort-dependency-features",
+                "sort-features",
+                "wrap-arrays",
+                "layout"
+            ]
+        );
+    }
+
+    #[test]
+    fn disabled_rules_do_not_run() {
+        let input = "[dependencies.serde]\nversion = \"1\"\n\n[dependencies]\nclap = \"4\"\nanyhow = \"1\"\n";
+        let mut config = Config::default();
+        config
+            .select_rules(&["sort-dependencies".to_string()], &[])
+            .expect("known rule");
+        let mut doc = input.parse::<DocumentMut>().expect("valid TOML");
+        let changes = crate::format_document(&mut doc, &config).expect("format succeeded");
+
+        assert_eq!(changes.len(), 1, "changes: {changes:?}");
+        assert_eq!(changes[0].rule, "sort-dependencies");
+        assert!(
+            doc.to_string().contains("[dependencies.serde]"),
+            "collapse should not run:\n{doc}"
+        );
+    }
+
+    #[test]
+    fn sorting_keeps_comments_with_their_entry() {
+        for header in [
+            "[dependencies]",
+            "[dev-dependencies]",
+            "[build-dependencies]",
+            "[target.'cfg(unix)'.dependencies]",
+        ] {
+            let input = format!(
+                "{header}\n# pinned because of CVE-2024-0001\ntokio = \"=1.0.1\" # see advisory\nanyhow = \"1\" # errors\n"
+            );
+            let result = full_format(&input);
+            let expected = format!(
+                "{header}\nanyhow = \"1\" # errors\n# pinned because of CVE-2024-0001\ntokio = \"=1.0.1\" # see advisory\n"
+            );
+            assert!(
+                result.contains(&expected),
+                "comments moved away from their entry in:\n{result}"
+            );
+        }
+    }
+
+    #[test]
+    fn collapsing_keeps_comments_with_their_entry() {
+        let input = "\
+[dependencies]
+anyhow = \"1\"
+
+# needs the derive feature
+[dependencies.serde] # see #123
+# minimum for derive
+version = \"1.0.100\" # pinned
+features = [\"derive\"]
+";
+        let result = full_format(input);
+        assert!(
+            result.contains(
+                "\
+anyhow = \"1\"
+# needs the derive feature
+# minimum for derive
+# pinned
+serde = { version = \"1.0.100\", features = [\"derive\"] } # see #123
+"
+            ),
+            "comments lost in:\n{result}"
+        );
+        assert!(
+            result.parse::<DocumentMut>().is_ok(),
+            "invalid TOML:\n{result}"
+        );
+    }
+
+    #[test]
+    fn package_order_keeps_comments_with_their_entry() {
+        let input = "\
+[package]
+version = \"0.1.0\" # bump on release
+# The crate name
+name = \"x\"
+";
+        let result = full_format(input);
+        assert_eq!(
+            result,
+            "\
+[package]
+# The crate name
+name = \"x\"
+version = \"0.1.0\" # bump on release
+"
+        );
+    }
+
+    #[test]
+    fn grouped_sort_keeps_groups_and_headers_in_place() {
+        let input = "\
+[dependencies]
+# async runtime
+tokio = \"1\"
+futures = \"0.3\"
+
+# serialization
+serde_json = \"1\"
+# pinned for MSRV
+serde = \"=1.0.100\"
+";
+        let mut doc = input.parse::<DocumentMut>().expect("valid TOML");
+        let config = Config::parse("dependency-sort = \"grouped\"\n").expect("valid config");
+        crate::format_document(&mut doc, &config).expect("format succeeded");
+
+        assert_eq!(
+            doc.to_string(),
+            "\
+[dependencies]
+# async runtime
+futures = \"0.3\"
+tokio = \"1\"
+
+# serialization
+# pinned for MSRV
+serde = \"=1.0.100\"
+serde_json = \"1\"
+"
+        );
+    }
+
+    #[test]
+    fn grouped_sort_reads_group_headers_of_dotted_entries() {
+        let input = "\
+[dependencies]
+# internal
+b.workspace = true
+
+# external
+serde.workspace = true
+anyhow.workspace = true
+";
+        let mut doc = input.parse::<DocumentMut>().expect("valid TOML");
+        let mut config = Config::parse("dependency-sort = \"grouped\"\n").expect("valid config");
+        config
+            .select_rules(&["sort-dependencies".to_string()], &[])
+            .expect("known rule");
+        crate::format_document(&mut doc, &config).expect("format succeeded");
+
+        assert_eq!(
+            doc.to_string(),
+            "\
+[dependencies]
+# internal
+b.workspace = true
+
+# external
+anyhow.workspace = true
+serde.workspace = true
+"
+        );
+        let table = doc["dependencies"].as_table_mut().expect("table");
+        assert_eq!(
+            sort_table_in_place(table, DependencySort::Grouped).expect("sorted"),
+            0
+        );
+    }
+
+    #[test]
+    fn grouped_sort_leaves_sorted_groups_alone() {
+        let input = "[dependencies]\nb = \"1\"\nc = \"1\"\n\na = \"1\"\n";
+        let mut doc = input.parse::<DocumentMut>().expect("valid TOML");
+        let table = doc["dependencies"].as_table_mut().expect("table");
+        assert_eq!(
+            sort_table_in_place(table, DependencySort::Grouped).expect("sorted"),
+            0
+        );
+        assert_eq!(doc.to_string(), input);
+    }
+
+    #[test]
+    fn sort_features_orders_names_and_entries() {
+        let input = "\
+[features]
+serde = [\"dep:serde\", \"uuid/serde\", \"std\", \"dep:serde\"]
+std = []
+default = [
+    \"std\",
+    # needed by the CLI
+    \"cli\",
+]
+cli = [\"dep:clap\"]
+";
+        let mut doc = input.parse::<DocumentMut>().expect("valid TOML");
+        let mut config = Config::default();
+        config
+            .select_rules(&["sort-features".to_string()], &[])
+            .expect("known rule");
+        let changes = crate::format_document(&mut doc, &config).expect("format succeeded");
+
+        assert_eq!(changes[0].rule, "sort-features");
+        assert_eq!(
+            doc.to_string(),
+            "\
+[features]
+default = [
+    # needed by the CLI
+    \"cli\",
+    \"std\",
+]
+cli = [\"dep:clap\"]
+serde = [\"std\", \"dep:serde\", \"uuid/serde\"]
+std = []
+"
+        );
+    }
+
+    #[test]
+    fn sort_features_is_off_by_default() {
+        let input = "[features]\nstd = []\ndefault = [\"std\"]\n";
+        assert_eq!(full_format(input), input);
+    }
+
+    #[test]
+    fn target_dependency_tables_are_sorted_and_collapsed() {
+        let input = "\
+[target.'cfg(windows)'.build-dependencies]
+winres = \"0.1\"
+cc = \"1\"
+
+[target.'cfg(unix)'.dev-dependencies]
+tempfile = \"3\"
+assert_cmd = \"2\"
+
+[target.'cfg(unix)'.dev-dependencies.nix]
+version = \"0.29\"
+";
+        let result = full_format(input);
+        assert_eq!(
+            result,
+            "\
+[target.'cfg(unix)'.dev-dependencies]
+assert_cmd = \"2\"
+nix = \"0.29\"
+tempfile = \"3\"
+
+[target.'cfg(windows)'.build-dependencies]
+cc = \"1\"
+winres = \"0.1\"
+"
+        );
+        assert_eq!(full_format(&result), result, "not idempotent");
+    }
+
+    #[test]
+    fn workspace_dependencies_are_sorted_and_collapsed() {
+        let input = "\
+[workspace]
+members = [\"crates/*\"]
+
+[workspace.dependencies]
+tokio = \"1\"
+anyhow = \"1\"
+
+# derive support
+[workspace.dependencies.serde]
+version = \"1\"
+features = [\"derive\"]
+";
+        let result = full_format(input);
+        assert_eq!(
+            result,
+            "\
+[workspace]
+members = [\"crates/*\"]
+
+[workspace.dependencies]
+anyhow = \"1\"
+# derive support
+serde = { version = \"1\", features = [\"derive\"] }
+tokio = \"1\"
+"
+        );
+    }
+
+    #[test]
+    fn dependency_specs_get_the_canonical_key_order() {
+        let input = "\
+[dependencies]
+serde = { features = [\"derive\"], version = \"1\", default-features = false }
+local = { optional = true, path = \"../local\" }
+
+[dependencies.tokio]
+features = [\"full\"]
+version = \"1\"
+
+[workspace.dependencies]
+clap = { features = [\"derive\"], version = \"4\" }
+";
+        let mut doc = input.parse::<DocumentMut>().expect("valid TOML");
+        let mut config = Config::default();
+        config
+            .select_rules(&["dependency-key-order".to_string()], &[])
+            .expect("known rule");
+        let changes = crate::format_document(&mut doc, &config).expect("format succeeded");
+
+        assert_eq!(changes[0].count, 4);
+        assert_eq!(
+            doc.to_string(),
+            "\
+[dependencies]
+serde = { version = \"1\", default-features = false, features = [\"derive\"] }
+local = { path = \"../local\", optional = true }
+
+[dependencies.tokio]
+version = \"1\"
+features = [\"full\"]
+
+[workspace.dependencies]
+clap = { version = \"4\", features = [\"derive\"] }
+"
+        );
+    }
+
+    #[test]
+    fn dependency_features_are_sorted_and_deduplicated() {
+        let input = "\
+[dependencies]
+serde = { version = \"1\", features = [\"std\", \"derive\", \"std\"] }
+
+[target.'cfg(unix)'.dev-dependencies.tokio]
+version = \"1\"
+features = [
+    \"rt\",
+    # for tokio::main
+    \"macros\",
+]
+
+[workspace.dependencies]
+clap = { version = \"4\", features = [\"env\", \"derive\"] }
+";
+        let mut doc = input.parse::<DocumentMut>().expect("valid TOML");
+        let mut config = Config::default();
+        config
+            .select_rules(&["sort-dependency-features".to_string()], &[])
+            .expect("known rule");
+        let changes = crate::format_document(&mut doc, &config).expect("format succeeded");
+
+        assert_eq!(changes[0].count, 3);
+        assert_eq!(
+            doc.to_string(),
+            "\
+[dependencies]
+serde = { version = \"1\", features = [\"derive\", \"std\"] }
+
+[target.'cfg(unix)'.dev-dependencies.tokio]
+version = \"1\"
+features = [
+    # for tokio::main
+    \"macros\",
+    \"rt\",
+]
+
+[workspace.dependencies]
+clap = { version = \"4\", features = [\"derive\", \"env\"] }
+"
+        );
+    }
+
+    #[test]
+    fn layout_normalizes_whitespace() {
+        let input = "\
+[package]
+name=\"x\"
+keywords = [ \"cargo\", \"toml\", \"format\", \"cli\", \"workspace\", \"manifest\", \"formatter\", \"lint\" ]
+[dependencies]
+anyhow   =   \"1\"
+
+
+clap = { version = \"4\", features = [\"derive\"] }
+";
+        let mut doc = input.parse::<DocumentMut>().expect("valid TOML");
+        let mut config = Config::parse("[layout]\ncolumn-width = 60\n").expect("valid config");
+        config
+            .select_rules(&["layout".to_string()], &[])
+            .expect("known rule");
+        let changes = crate::format_document(&mut doc, &config).expect("format succeeded");
+
+        assert_eq!(changes[0].rule, "layout");
+        assert_eq!(
+            doc.to_string(),
+            "\
+[package]
+name = \"x\"
+keywords = [
+    \"cargo\",
+    \"toml\",
+    \"format\",
+    \"cli\",
+    \"workspace\",
+    \"manifest\",
+    \"formatter\",
+    \"lint\",
+]
+
+[dependencies]
+anyhow = \"1\"
+
+clap = { version = \"4\", features = [\"derive\"] }
+"
+        );
+        let again = crate::format_document(&mut doc, &config).expect("format succeeded");
+        assert!(again.is_empty(), "not idempotent: {again:?}");
+    }
+
+    #[test]
+    fn layout_leaves_formatted_manifests_alone() {
+        let input = "\
+[package]
+name = \"x\"
+keywords = [
+    \"cargo\",
+    \"toml\",
+    \"format\",
+    \"cli\",
+    \"workspace\",
+    \"manifest\",
+    \"formatter\",
+    \"lint\",
+]
+
+[dependencies]
+anyhow = \"1\"
+";
+        let mut config = Config::parse("[layout]\ncolumn-width = 60\n").expect("valid config");
+        config
+            .select_rules(&["layout".to_string()], &[])
+            .expect("known rule");
+        let mut doc = input.parse::<DocumentMut>().expect("valid TOML");
+        for _ in 0..2 {
+            let changes = crate::format_document(&mut doc, &config).expect("format succeeded");
+            assert!(changes.is_empty(), "unexpected changes: {changes:?}");
+            assert_eq!(doc.to_string(), input);
+        }
+    }
+
+    #[test]
+    fn wrap_arrays_expands_long_and_collapses_short_arrays() {
+        let input = "\
+[package]
+keywords = [\"cargo\", \"toml\", \"format\", \"cli\", \"workspace\"]
+categories = [
+    \"command-line-utilities\",
+]
+exclude = [
+    # generated
+    \"target/\",
+]
+
+[dependencies]
+clap = { version = \"4\", features = [\"derive\", \"env\", \"std\"] }
+";
+        let mut doc = input.parse::<DocumentMut>().expect("valid TOML");
+        let mut config = Config::parse("[layout]\ncolumn-width = 50\n").expect("valid config");
+        config
+            .select_rules(&["wrap-arrays".to_string()], &[])
+            .expect("known rule");
+        let changes = crate::format_document(&mut doc, &config).expect("format succeeded");
+
+        assert_eq!(changes[0].count, 3);
+        assert_eq!(
+            doc.to_string(),
+            "\
+[package]
+keywords = [
+    \"cargo\",
+    \"toml\",
+    \"format\",
+    \"cli\",
+    \"workspace\",
+]
+categories = [\"command-line-utilities\"]
+exclude = [
+    # generated
+    \"target/\",
+]
+
+[dependencies]
+clap = { version = \"4\", features = [
+    \"derive\",
+    \"env\",
+    \"std\",
+] }
+"
+        );
+        let again = crate::format_document(&mut doc, &config).expect("format succeeded");
+        assert!(again.is_empty(), "not idempotent: {again:?}");
+    }
+
+    #[test]
+    fn wrap_arrays_gives_way_to_layout() {
+        let input = "\
+[package]
+name = \"x\"
+keywords = [\"cargo\", \"toml\", \"format\", \"cli\", \"workspace\"]
+
+[dependencies]
+clap = { version = \"4\", features = [\"derive\", \"env\", \"std\"] }
+";
+        let config = Config::parse(
+            "\
+[layout]
+column-width = 40
+
+[rules]
+wrap-arrays = true
+layout = true
+",
+        )
+        .expect("valid config");
+        let first = crate::format_str(input, &config).expect("format succeeded");
+        assert!(
+            first.changes.iter().all(|c| c.rule != "wrap-arrays"),
+            "wrap-arrays ran alongside layout: {:?}",
+            first.changes
+        );
+
+        let second = crate::format_str(&first.output, &config).expect("format succeeded");
+        assert!(!second.changed(), "not idempotent: {:?}", second.changes);
+    }
+
+    #[test]
+    fn oversized_inline_dependencies_become_tables() {
+        let input = "\
+[dependencies]
+anyhow = \"1\"
+# async runtime
+tokio = { version = \"1\", default-features = false, features = [\"rt\"] } # pinned
+
+[dependencies.serde]
+version = \"1\"
+features = [\"derive\"]
+
+[dependencies.clap]
+version = \"4\"
+default-features = false
+features = [\"derive\", \"std\"]
+";
+        let mut doc = input.parse::<DocumentMut>().expect("valid TOML");
+        let config = Config::parse("inline-table-max-keys = 2\n").expect("valid config");
+        let changes = crate::format_document(&mut doc, &config).expect("format succeeded");
+
+        assert!(
+            changes
+                .iter()
+                .any(|c| c.rule == "expand-inline-tables" && c.count == 1),
+            "expected one expansion in {changes:?}"
+        );
+        assert_eq!(
+            doc.to_string(),
+            "\
+[dependencies]
+anyhow = \"1\"
+serde = { version = \"1\", features = [\"derive\"] }
+
+[dependencies.clap]
+version = \"4\"
+default-features = false
+features = [\"derive\", \"std\"]
+
+# async runtime
+[dependencies.tokio] # pinned
+version = \"1\"
+default-features = false
+features = [\"rt\"]
+"
+        );
+        let again = crate::format_document(&mut doc, &config).expect("format succeeded");
+        assert!(again.is_empty(), "not idempotent: {again:?}");
+    }
+
+    #[test]
+    fn inline_table_width_limit_ignores_array_wrapping() {
+        let input = "\
+[dependencies]
+clap = { version = \"4\", features = [
+    \"derive\",
+    \"env\",
+] }
+serde = \"1\"
+";
+        let mut doc = input.parse::<DocumentMut>().expect("valid TOML");
+        let mut config = Config::parse("inline-table-max-width = 40\n").expect("valid config");
+        config
+            .select_rules(&["expand-inline-tables".to_string()], &[])
+            .expect("known rule");
+        crate::format_document(&mut doc, &config).expect("format succeeded");
+
+        assert_eq!(
+            doc.to_string(),
+            "\
+[dependencies]
+serde = \"1\"
+
+[dependencies.clap]
+version = \"4\"
+features = [
+    \"derive\",
+    \"env\",
+]
+"
+        );
+    }
+
+    #[test]
+    fn expanded_tables_sort_stably_across_runs() {
+        let input = "\
+[dependencies]
+aaa = { version = \"1\", default-features = false, features = [\"std\"] }
+zzz = \"1\"
+
+[target.'cfg(unix)'.dependencies]
+libc = { version = \"0.2\", default-features = false, features = [\"std\"] }
+";
+        let config = Config::parse("inline-table-max-keys = 2\n").expect("valid config");
+        let first = crate::format_str(input, &config).expect("format succeeded");
+        assert_eq!(
+            first.output,
+            "\
+[dependencies]
+zzz = \"1\"
+
+[dependencies.aaa]
+version = \"1\"
+default-features = false
+features = [\"std\"]
+
+[target.'cfg(unix)'.dependencies.libc]
+version = \"0.2\"
+default-features = false
+features = [\"std\"]
+"
+        );
+
+        // The second run starts from the written text, where the
+        // sub-table follows the entries whatever its name.
+        let second = crate::format_str(&first.output, &config).expect("format succeeded");
+        assert!(!second.changed(), "not idempotent: {:?}", second.changes);
+        assert_eq!(second.output, first.output);
+    }
+
+    #[test]
+    fn redundant_dependency_specs_are_simplified() {
+        let input = "\
+[dependencies]
+anyhow = { version = \"1\" } # pinned
+log = { version = \"0.4\", optional = false, default-features = true }
+serde = { workspace = true, default-features = true, optional = false }
+tokio.workspace = true
+
+[dependencies.clap]
+workspace = true
+";
+        let result = full_format(input);
+        assert_eq!(
+            result,
+            "\
+[dependencies]
+anyhow = \"1\" # pinned
+clap = { workspace = true }
+log = \"0.4\"
+serde = { workspace = true, default-features = true }
+tokio = { workspace = true }
+"
+        );
+        assert_eq!(full_format(&result), result, "not idempotent");
+    }
+
+    #[test]
+    fn inheritance_style_is_applied_in_both_directions() {
+        let input = "\
+[package]
+name = \"app\"
+version.workspace = true
+edition = { workspace = true }
+
+[dependencies]
+# shared
+clap = { workspace = true } # cli
+serde.workspace = true
+serde.features = [\"derive\"]
+";
+        let dotted = "\
+[package]
+name = \"app\"
+version.workspace = true
+edition.workspace = true
+
+[dependencies]
+# shared
+clap.workspace = true # cli
+serde.workspace = true
+serde.features = [\"derive\"]
+";
+        let inline = "\
+[package]
+name = \"app\"
+version = { workspace = true }
+edition = { workspace = true }
+
+[dependencies]
+# shared
+clap = { workspace = true } # cli
+serde = { workspace = true, features = [\"derive\"] }
+";
+        let mut config =
+            Config::parse("workspace-inheritance = \"dotted\"\n").expect("valid config");
+        config
+            .select_rules(&["inheritance-style".to_string()], &[])
+            .expect("known rule");
+        let mut doc = input.parse::<DocumentMut>().expect("valid TOML");
+        crate::format_document(&mut doc, &config).expect("format succeeded");
+        assert_eq!(doc.to_string(), dotted);
+
+        config.workspace_inheritance = InheritanceStyle::Inline;
+        let changes = crate::format_document(&mut doc, &config).expect("format succeeded");
+        assert_eq!(changes[0].count, 4);
+        assert_eq!(doc.to_string(), inline);
+        let again = crate::format_document(&mut doc, &config).expect("format succeeded");
+        assert!(again.is_empty(), "not idempotent: {again:?}");
+    }
+
+    #[test]
+    fn package_keys_follow_the_full_cargo_order() {
+        let input = "\
+[workspace.package]
+repository = \"https://example.com/app\"
+license = \"MIT\"
+edition = \"2024\"
+
+[package]
+metadata = { release = false }
+publish = false
+custom-key = 1
+keywords = [\"cli\"]
+build = \"build.rs\"
+name = \"app\"
+homepage = \"https://example.com\"
+repository.workspace = true
+license.workspace = true
+";
+        let mut doc = input.parse::<DocumentMut>().expect("valid TOML");
+        let mut config = Config::default();
+        config
+            .select_rules(&["package-order".to_string()], &[])
+            .expect("known rule");
+        crate::format_document(&mut doc, &config).expect("format succeeded");
+
+        assert_eq!(
+            doc.to_string(),
+            "\
+[workspace.package]
+edition = \"2024\"
+license = \"MIT\"
+repository = \"https://example.com/app\"
+
+[package]
+name = \"app\"
+license.workspace = true
+homepage = \"https://example.com\"
+repository.workspace = true
+keywords = [\"cli\"]
+publish = false
+build = \"build.rs\"
+custom-key = 1
+metadata = { release = false }
+"
+        );
+    }
+
+    #[test]
+    fn package_metadata_tables_follow_the_package_header() {
+        let input = "\
+[package.metadata.docs.rs]
+all-features = true
+
+[package]
+version = \"0.1.0\"
+name = \"x\"
+";
+        let mut doc = input.parse::<DocumentMut>().expect("valid TOML");
+        crate::format_document(&mut doc, &Config::default()).expect("format succeeded");
+        let first = doc.to_string();
+        assert_eq!(
+            first,
+            "\
+[package]
+name = \"x\"
+version = \"0.1.0\"
+
+[package.metadata.docs.rs]
+all-features = true
+"
+        );
+
+        let mut doc = first.parse::<DocumentMut>().expect("valid TOML");
+        let changes =
+            crate::format_document(&mut doc, &Config::default()).expect("format succeeded");
+        assert!(changes.is_empty(), "not idempotent: {changes:?}");
+        assert_eq!(doc.to_string(), first);
+    }
+
+    #[test]
+    fn workspace_header_moves_above_its_sub_tables() {
+        let input = "\
+[workspace.package]
+edition = \"2024\"
+
+[workspace.dependencies]
+anyhow = \"1\"
+
+[workspace]
+members = [\"crates/app\"]
+resolver = \"3\"
+";
+        let mut doc = input.parse::<DocumentMut>().expect("valid TOML");
+        crate::format_document(&mut doc, &Config::default()).expect("format succeeded");
+        let first = doc.to_string();
+        assert_eq!(
+            first,
+            "\
+[workspace]
+resolver = \"3\"
+members = [\"crates/app\"]
+
+[workspace.package]
+edition = \"2024\"
+
+[workspace.dependencies]
+anyhow = \"1\"
+"
+        );
+
+        let mut doc = first.parse::<DocumentMut>().expect("valid TOML");
+        let changes =
+            crate::format_document(&mut doc, &Config::default()).expect("format succeeded");
+        assert!(changes.is_empty(), "not idempotent: {changes:?}");
+        assert_eq!(doc.to_string(), first);
+    }
+
+    #[test]
+    fn workspace_keys_are_ordered_and_members_sorted() {
+        let input = "\
+[workspace]
+exclude = [\"old\", \"legacy\", \"old\"]
+members = [
+    \"crates/zeta\",
+    # the core
+    \"crates/alpha\",
+    \"crates/zeta\",
+]
+resolver = \"3\"
+
+[workspace.metadata.fmt-toml]
+dependency-sort = \"grouped\"
+
+[workspace.dependencies]
+anyhow = \"1\"
+
+[workspace.package]
+edition = \"2024\"
+";
+        let result = full_format(input);
+        assert_eq!(
+            result,
+            "\
+[workspace]
+resolver = \"3\"
+members = [
+    # the core
+    \"crates/alpha\",
+    \"crates/zeta\",
+]
+exclude = [\"legacy\", \"old\"]
+
+[workspace.package]
+edition = \"2024\"
+
+[workspace.dependencies]
+anyhow = \"1\"
+
+[workspace.metadata.fmt-toml]
+dependency-sort = \"grouped\"
+"
+        );
+        assert_eq!(full_format(&result), result, "not idempotent");
+    }
+}
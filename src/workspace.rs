@@ -0,0 +1,1173 @@
+//! Workspace discovery and the rules that span several manifests:
+//! moving dependency versions and internal path dependencies into the
+//! root `[workspace.dependencies]` table, and inheriting `[package]`
+//! fields from `[workspace.package]`.
+
+use std::collections::BTreeMap;
+use std::path::{
+    Component,
+    Path,
+    PathBuf,
+};
+
+use anyhow::Result;
+use toml_edit::{
+    InlineTable,
+    Item,
+    Value,
+};
+
+use crate::rules::{
+    dependency_tables_mut,
+    replace_with_value,
+};
+use crate::{
+    Config,
+    Manifest,
+    Warning,
+    record,
+};
+
+/// A workspace package, as reported by cargo metadata.
+struct WorkspaceMember {
+    /// Directory containing the package's Cargo.toml.
+    dir: PathBuf,
+    version: String,
+}
+
+/// The manifests to format, loaded into memory together with the
+/// configuration that applies to them.
+pub struct Workspace {
+    /// Manifests to format, including a virtual workspace root.
+    pub manifests: Vec<Manifest>,
+    /// Configuration found for the manifests.
+    pub config: Config,
+    /// Index of the root manifest in `manifests`; `None` when only some
+    /// files were loaded.
+    root_index: Option<usize>,
+    members: Vec<WorkspaceMember>,
+}
+
+impl Workspace {
+    /// Load every manifest of the workspace at `workspace_path`,
+    /// including a virtual root.
+    pub fn load(workspace_path: &Path) -> Result<Self> {
+        // Use cargo_metadata to find the workspace root and its members
+        let metadata = cargo_plugin_utils::get_metadata(Some(&workspace_path.join("Cargo.toml")))?;
+        let root_manifest = metadata.workspace_root.as_std_path().join("Cargo.toml");
+        let config = Config::discover(metadata.workspace_root.as_std_path())?;
+
+        let packages = metadata.workspace_packages();
+        let crate_manifests: Vec<PathBuf> = packages
+            .iter()
+            .map(|pkg| pkg.manifest_path.as_std_path().to_path_buf())
+            .collect();
+        let members: Vec<WorkspaceMember> = packages
+            .iter()
+            .map(|pkg| WorkspaceMember {
+                dir: manifest_dir(pkg.manifest_path.as_std_path()).to_path_buf(),
+                version: pkg.version.to_string(),
+            })
+            .collect();
+
+        let mut manifests: Vec<Manifest> = crate_manifests
+            .iter()
+            .map(|path| Manifest::open(path))
+            .collect();
+        // A virtual workspace root has no package of its own, so it is
+        // not in the member list. Format it too; when the root is itself
+        // a package it is already in the list and must not be loaded
+        // twice.
+        let root_index = match manifests.iter().position(|m| m.path == root_manifest) {
+            Some(index) => index,
+            None => {
+                manifests.push(Manifest::open(&root_manifest));
+                manifests.len() - 1
+            }
+        };
+
+        Ok(Self {
+            manifests,
+            config,
+            root_index: Some(root_index),
+            members,
+        })
+    }
+
+    /// Load just the manifests given on the command line, without asking
+    /// cargo for the workspace layout. The configuration is found from
+    /// the first file's location.
+    pub fn load_files(files: &[PathBuf]) -> Result<Self> {
+        let config = match files.first() {
+            Some(first) => Config::for_manifest(first)?,
+            None => Config::default(),
+        };
+
+        let mut manifests: Vec<Manifest> = Vec::new();
+        for path in files {
+            // Pre-commit hooks may list the same file more than once.
+            if !manifests.iter().any(|m| &m.path == path) {
+                manifests.push(Manifest::open(path));
+            }
+        }
+
+        Ok(Self {
+            manifests,
+            config,
+            root_index: None,
+            members: Vec::new(),
+        })
+    }
+
+    /// Every workspace-level rule, in the order they run.
+    pub fn rules() -> Vec<Box<dyn WorkspaceRule>> {
+        vec![
+            Box::new(InternalDependencies),
+            Box::new(HoistDependencies),
+            Box::new(InheritPackageFields),
+        ]
+    }
+
+    /// Apply the enabled rules that span several manifests. They need
+    /// every member, so nothing happens when only some files were
+    /// loaded.
+    pub fn apply_workspace_rules(&mut self) {
+        if self.root_index.is_none() {
+            return;
+        }
+        for rule in Self::rules() {
+            if self.config.is_enabled(rule.id()) {
+                rule.apply(self);
+            }
+        }
+    }
+}
+
+/// A formatting rule that rewrites several manifests of a workspace at
+/// once. Unlike a [`Rule`](crate::Rule) it needs the root manifest and
+/// every member, and it records its changes on each manifest it touches
+/// itself, as the root and the members get different messages.
+pub trait WorkspaceRule {
+    /// Identifier used in the `rules` table of the configuration and by
+    /// `--rules` and `--skip-rule`.
+    fn id(&self) -> &'static str;
+
+    /// One-line description of what the rule does.
+    fn description(&self) -> &'static str;
+
+    /// Apply the rule to the manifests of `workspace`.
+    fn apply(&self, workspace: &mut Workspace);
+}
+
+/// Makes `path` dependencies on workspace members inherit them from
+/// `[workspace.dependencies]`.
+pub struct InternalDependencies;
+
+impl WorkspaceRule for InternalDependencies {
+    fn id(&self) -> &'static str {
+        "internal-dependencies"
+    }
+
+    fn description(&self) -> &'static str {
+        "Switch internal path dependencies to { workspace = true }"
+    }
+
+    fn apply(&self, workspace: &mut Workspace) {
+        if let Some(root_index) = workspace.root_index {
+            inherit_internal_dependencies(&mut workspace.manifests, root_index, &workspace.members);
+        }
+    }
+}
+
+/// Moves the versions of member dependencies into
+/// `[workspace.dependencies]`.
+pub struct HoistDependencies;
+
+impl WorkspaceRule for HoistDependencies {
+    fn id(&self) -> &'static str {
+        "hoist-dependencies"
+    }
+
+    fn description(&self) -> &'static str {
+        "Hoist dependency versions into [workspace.dependencies]"
+    }
+
+    fn apply(&self, workspace: &mut Workspace) {
+        if let Some(root_index) = workspace.root_index {
+            hoist_workspace_dependencies(&mut workspace.manifests, root_index);
+        }
+    }
+}
+
+/// Makes member `[package]` fields that repeat `[workspace.package]`
+/// inherit them.
+pub struct InheritPackageFields;
+
+impl WorkspaceRule for InheritPackageFields {
+    fn id(&self) -> &'static str {
+        "inherit-package-fields"
+    }
+
+    fn description(&self) -> &'static str {
+        "Inherit [package] fields that repeat [workspace.package]"
+    }
+
+    fn apply(&self, workspace: &mut Workspace) {
+        if let Some(root_index) = workspace.root_index {
+            inherit_package_fields(&mut workspace.manifests, root_index);
+        }
+    }
+}
+
+/// `[package]` fields that hold a path relative to their manifest.
+const PATH_FIELDS: [&str; 2] = ["license-file", "readme"];
+
+/// Keys of a dependency spec that identify which crate is used. When a
+/// dependency is hoisted these move to `[workspace.dependencies]`; all
+/// other keys (features, optional, ...) stay with the member.
+const HOISTED_KEYS: [&str; 4] = ["version", "package", "registry", "default-features"];
+
+/// A dependency spec split into the keys shared through
+/// `[workspace.dependencies]` and the keys local to the member.
+struct DependencySpec {
+    shared: Vec<(String, Value)>,
+    local: Vec<(String, Value)>,
+}
+
+/// Move the version of every versioned member dependency into the root
+/// `[workspace.dependencies]` table and make the member inherit it with
+/// `{ workspace = true }`. Existing workspace entries are reused when
+/// they describe the same crate; conflicting entries are left alone.
+fn hoist_workspace_dependencies(manifests: &mut [Manifest], root_index: usize) {
+    inherit_from_workspace(
+        manifests,
+        root_index,
+        RuleMessages {
+            rule: "hoist-dependencies",
+            member: "Hoisted dependency versions into [workspace.dependencies]",
+            root: "Added hoisted dependencies to [workspace.dependencies]",
+        },
+        |_manifest_dir, item| split_versioned_dependency(item),
+        |existing, _item, spec| shared_signature(existing) == spec_signature(&spec.shared),
+    );
+}
+
+/// Register every `path` dependency that points at a workspace member
+/// in `[workspace.dependencies]` (with its path and version) and make
+/// the member inherit it with `{ workspace = true }`. An existing entry
+/// is reused when it has the same path and, if the member requires a
+/// version, the same version; otherwise the dependency is kept and
+/// reported.
+fn inherit_internal_dependencies(
+    manifests: &mut [Manifest],
+    root_index: usize,
+    members: &[WorkspaceMember],
+) {
+    let root_dir = manifest_dir(&manifests[root_index].path).to_path_buf();
+    inherit_from_workspace(
+        manifests,
+        root_index,
+        RuleMessages {
+            rule: "internal-dependencies",
+            member: "Switched internal dependencies to { workspace = true }",
+            root: "Added internal dependencies to [workspace.dependencies]",
+        },
+        |manifest_dir, item| split_internal_dependency(item, manifest_dir, &root_dir, members),
+        |existing, item, spec| {
+            let existing_path = existing
+                .get("path")
+                .and_then(|p| p.as_str())
+                .map(|p| normalize_path(Path::new(p)));
+            let spec_path = spec
+                .shared
+                .iter()
+                .find(|(key, _)| key == "path")
+                .and_then(|(_, value)| value.as_str())
+                .map(|p| normalize_path(Path::new(p)));
+            let version = |item: &Item| {
+                item.get("version")
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string)
+            };
+            let same_version =
+                version(item).is_none_or(|required| version(existing) == Some(required));
+            existing_path.is_some() && existing_path == spec_path && same_version
+        },
+    );
+}
+
+/// How one of the workspace-level dependency rules records its changes.
+struct RuleMessages {
+    /// Id of the rule.
+    rule: &'static str,
+    /// Recorded on each member whose dependencies were rewritten.
+    member: &'static str,
+    /// Recorded on the root when entries were added to
+    /// `[workspace.dependencies]`.
+    root: &'static str,
+}
+
+/// Shared driver for the workspace-level dependency rules. `split`
+/// decides which member dependencies move to `[workspace.dependencies]`
+/// and `matches` decides whether an existing workspace entry can be
+/// reused for a member dependency, given the entry, the member's item
+/// and its split spec. Changes are recorded as described by `messages`;
+/// conflicts are recorded as warnings.
+fn inherit_from_workspace(
+    manifests: &mut [Manifest],
+    root_index: usize,
+    messages: RuleMessages,
+    split: impl Fn(&Path, &Item) -> Option<DependencySpec>,
+    matches: impl Fn(&Item, &Item, &DependencySpec) -> bool,
+) {
+    let Some(workspace) = manifests[root_index]
+        .doc
+        .get("workspace")
+        .and_then(|w| w.as_table())
+    else {
+        return;
+    };
+    let mut workspace_deps = workspace
+        .get("dependencies")
+        .and_then(|d| d.as_table())
+        .cloned()
+        .unwrap_or_default();
+
+    let mut added = 0;
+    for manifest in manifests.iter_mut() {
+        let dir = manifest_dir(&manifest.path).to_path_buf();
+        let mut count = 0;
+        for table in dependency_tables_mut(&mut manifest.doc) {
+            let keys: Vec<String> = table.iter().map(|(k, _)| k.to_string()).collect();
+            for key in &keys {
+                let Some(item) = table.get(key) else {
+                    continue;
+                };
+                let Some(spec) = split(&dir, item) else {
+                    continue;
+                };
+
+                match workspace_deps.get(key) {
+                    Some(existing) if !matches(existing, item, &spec) => {
+                        manifest.warnings.push(Warning {
+                            rule: messages.rule,
+                            message: format!(
+                                "Kept {}: it differs from [workspace.dependencies]",
+                                key
+                            ),
+                        });
+                        continue;
+                    }
+                    Some(_) => {}
+                    None => {
+                        workspace_deps.insert(key, Item::Value(workspace_dependency(spec.shared)));
+                        added += 1;
+                    }
+                }
+
+                replace_with_value(table, key, inherited_dependency(spec.local));
+                count += 1;
+            }
+        }
+        record(&mut manifest.changes, messages.rule, count, messages.member);
+    }
+
+    if added > 0 {
+        let root = &mut manifests[root_index];
+        if let Some(workspace) = root.doc.get_mut("workspace").and_then(|w| w.as_table_mut()) {
+            workspace_deps.set_implicit(false);
+            workspace.insert("dependencies", Item::Table(workspace_deps));
+            record(&mut root.changes, messages.rule, added, messages.root);
+        }
+    }
+}
+
+/// Make every member `[package]` field that is also defined in
+/// `[workspace.package]` inherit it with `{ workspace = true }` when the
+/// member repeats the workspace value. Members with a different value
+/// are left alone and reported.
+fn inherit_package_fields(manifests: &mut [Manifest], root_index: usize) {
+    let Some(workspace_package) = manifests[root_index]
+        .doc
+        .get("workspace")
+        .and_then(|w| w.get("package"))
+        .and_then(|p| p.as_table())
+        .cloned()
+    else {
+        return;
+    };
+    let root_dir = manifest_dir(&manifests[root_index].path).to_path_buf();
+
+    for manifest in manifests.iter_mut() {
+        let dir = manifest_dir(&manifest.path).to_path_buf();
+        let Some(package) = manifest
+            .doc
+            .get_mut("package")
+            .and_then(|p| p.as_table_mut())
+        else {
+            continue;
+        };
+
+        let mut count = 0;
+        for (key, workspace_item) in workspace_package.iter() {
+            let (Some(workspace_value), Some(value)) = (
+                workspace_item.as_value(),
+                package.get(key).and_then(|item| item.as_value()),
+            ) else {
+                continue;
+            };
+            if value.is_inline_table() {
+                continue;
+            }
+
+            let same = if PATH_FIELDS.contains(&key)
+                && let (Some(workspace_path), Some(path)) =
+                    (workspace_value.as_str(), value.as_str())
+            {
+                normalize_path(&root_dir.join(workspace_path)) == normalize_path(&dir.join(path))
+            } else {
+                same_value(workspace_value, value)
+            };
+            if !same {
+                manifest.warnings.push(Warning {
+                    rule: "inherit-package-fields",
+                    message: format!(
+                        "Kept [package] {}: it differs from [workspace.package]",
+                        key
+                    ),
+                });
+                continue;
+            }
+
+            replace_with_value(package, key, inherited_dependency(Vec::new()));
+            count += 1;
+        }
+        record(
+            &mut manifest.changes,
+            "inherit-package-fields",
+            count,
+            "Inherited [package] fields from [workspace.package]",
+        );
+    }
+}
+
+/// Whether two values are equal, ignoring how they are written.
+fn same_value(left: &Value, right: &Value) -> bool {
+    let parse = |value: &Value| format!("value = {value}").parse::<toml::Table>().ok();
+    match (parse(left), parse(right)) {
+        (Some(left), Some(right)) => left == right,
+        _ => false,
+    }
+}
+
+/// Split a versioned dependency into its shared and local keys.
+/// Returns `None` for dependencies that cannot be hoisted: workspace,
+/// path and git dependencies, and specs without a version.
+fn split_versioned_dependency(item: &Item) -> Option<DependencySpec> {
+    if let Some(version) = item.as_str() {
+        return Some(DependencySpec {
+            shared: vec![("version".to_string(), Value::from(version))],
+            local: Vec::new(),
+        });
+    }
+
+    let table = item.as_table_like()?;
+    if !table.contains_key("version")
+        || ["workspace", "path", "git"]
+            .iter()
+            .any(|k| table.contains_key(k))
+    {
+        return None;
+    }
+
+    let mut spec = DependencySpec {
+        shared: Vec::new(),
+        local: Vec::new(),
+    };
+    for (key, child) in table.iter() {
+        let mut value = child.as_value()?.clone();
+        value.decor_mut().clear();
+        if HOISTED_KEYS.contains(&key) {
+            spec.shared.push((key.to_string(), value));
+        } else {
+            spec.local.push((key.to_string(), value));
+        }
+    }
+    Some(spec)
+}
+
+/// Split a `path` dependency that points at a workspace member into
+/// its shared and local keys. The shared part holds the path relative to
+/// the workspace root and the member's version, unless the dependency
+/// already requires a specific version.
+fn split_internal_dependency(
+    item: &Item,
+    manifest_dir: &Path,
+    root_dir: &Path,
+    members: &[WorkspaceMember],
+) -> Option<DependencySpec> {
+    let table = item.as_table_like()?;
+    if table.contains_key("workspace") || table.contains_key("git") {
+        return None;
+    }
+    let target = normalize_path(&manifest_dir.join(table.get("path")?.as_str()?));
+    let member = members.iter().find(|m| normalize_path(&m.dir) == target)?;
+    let relative = target.strip_prefix(normalize_path(root_dir)).ok()?;
+
+    let mut spec = DependencySpec {
+        shared: vec![(
+            "path".to_string(),
+            Value::from(relative.to_string_lossy().replace('\\', "/")),
+        )],
+        local: Vec::new(),
+    };
+    if !table.contains_key("version") {
+        spec.shared
+            .push(("version".to_string(), Value::from(member.version.as_str())));
+    }
+    for (key, child) in table.iter() {
+        if key == "path" {
+            continue;
+        }
+        let mut value = child.as_value()?.clone();
+        value.decor_mut().clear();
+        if HOISTED_KEYS.contains(&key) {
+            spec.shared.push((key.to_string(), value));
+        } else {
+            spec.local.push((key.to_string(), value));
+        }
+    }
+    Some(spec)
+}
+
+/// Directory containing a manifest; `.` for a bare file name such as
+/// `Cargo.toml`.
+pub(crate) fn manifest_dir(manifest_path: &Path) -> &Path {
+    match manifest_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+/// Lexically resolve `.` and `..` components, so that paths written
+/// relative to different manifests can be compared without touching
+/// the file system.
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// Normalized view of the shared keys of a dependency spec, used to
+/// decide whether two specs describe the same crate.
+fn spec_signature(shared: &[(String, Value)]) -> BTreeMap<String, String> {
+    shared
+        .iter()
+        .filter(|(key, value)| !(key == "default-features" && value.as_bool() == Some(true)))
+        .map(|(key, value)| (key.clone(), value.to_string().trim().to_string()))
+        .collect()
+}
+
+/// Signature of an existing `[workspace.dependencies]` entry, ignoring
+/// the keys that members may add on top of it.
+fn shared_signature(item: &Item) -> BTreeMap<String, String> {
+    if let Some(version) = item.as_str() {
+        return spec_signature(&[("version".to_string(), Value::from(version))]);
+    }
+    let Some(table) = item.as_table_like() else {
+        return BTreeMap::new();
+    };
+    let shared: Vec<(String, Value)> = table
+        .iter()
+        .filter(|(key, _)| !["features", "optional", "public"].contains(key))
+        .filter_map(|(key, child)| Some((key.to_string(), child.as_value()?.clone())))
+        .collect();
+    spec_signature(&shared)
+}
+
+/// Build a `[workspace.dependencies]` entry: a plain version string when
+/// only the version is shared, an inline table otherwise.
+fn workspace_dependency(shared: Vec<(String, Value)>) -> Value {
+    if let [(key, version)] = shared.as_slice()
+        && key == "version"
+    {
+        return version.clone();
+    }
+    let mut inline = InlineTable::new();
+    for (key, value) in shared {
+        inline.insert(key, value);
+    }
+    Value::InlineTable(inline)
+}
+
+/// Build a member entry that inherits from the workspace, keeping the
+/// member-local keys.
+fn inherited_dependency(local: Vec<(String, Value)>) -> Value {
+    let mut inline = InlineTable::new();
+    inline.insert("workspace", Value::from(true));
+    for (key, value) in local {
+        inline.insert(key, value);
+    }
+    Value::InlineTable(inline)
+}
+
+#[cfg(test)]
+mod tests {
+    use toml_edit::DocumentMut;
+
+    use super::*;
+
+    /// Helper that builds an in-memory manifest from a TOML string.
+    fn manifest(path: &str, input: &str) -> Manifest {
+        Manifest {
+            path: PathBuf::from(path),
+            original: input.to_string(),
+            doc: input.parse::<DocumentMut>().expect("valid TOML"),
+            changes: Vec::new(),
+            warnings: Vec::new(),
+            formatted: None,
+            error: None,
+        }
+    }
+
+    /// The messages of the warnings recorded on `manifest`.
+    fn warning_messages(manifest: &Manifest) -> Vec<&str> {
+        manifest
+            .warnings
+            .iter()
+            .map(|warning| warning.message.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn hoist_moves_versions_to_workspace_dependencies() {
+        let root = manifest(
+            "Cargo.toml",
+            "\
+[workspace]
+members = [\"crate-a\"]
+
+[workspace.dependencies]
+anyhow = \"1.0\"
+",
+        );
+        let member = manifest(
+            "crate-a/Cargo.toml",
+            "\
+[package]
+name = \"crate-a\"
+
+[dependencies]
+anyhow = \"1.0\"
+serde = { version = \"1.0\", features = [\"derive\"], optional = true }
+clap = { version = \"4\", default-features = false }
+
+[target.'cfg(unix)'.dev-dependencies]
+libc = \"0.2\" # needed for signals
+",
+        );
+        let mut manifests = vec![root, member];
+        hoist_workspace_dependencies(&mut manifests, 0);
+
+        let root = manifests[0].doc.to_string();
+        let member = manifests[1].doc.to_string();
+        assert_eq!(manifests[0].change_count(), 3, "root:\n{root}");
+        assert_eq!(manifests[1].change_count(), 4, "member:\n{member}");
+        assert_eq!(
+            manifests[0].changes[0].message,
+            "Added hoisted dependencies to [workspace.dependencies]"
+        );
+        assert!(root.contains("anyhow = \"1.0\""), "root:\n{root}");
+        assert!(root.contains("serde = \"1.0\""), "root:\n{root}");
+        assert!(
+            root.contains("clap = { version = \"4\", default-features = false }"),
+            "root:\n{root}"
+        );
+        assert!(root.contains("libc = \"0.2\""), "root:\n{root}");
+        assert!(
+            member.contains("anyhow = { workspace = true }"),
+            "member:\n{member}"
+        );
+        assert!(
+            member
+                .contains("serde = { workspace = true, features = [\"derive\"], optional = true }"),
+            "member:\n{member}"
+        );
+        assert!(
+            member.contains("clap = { workspace = true }"),
+            "member:\n{member}"
+        );
+        assert!(
+            member.contains("libc = { workspace = true } # needed for signals"),
+            "member:\n{member}"
+        );
+    }
+
+    #[test]
+    fn hoist_keeps_conflicting_and_non_registry_dependencies() {
+        let root = manifest(
+            "Cargo.toml",
+            "\
+[workspace]
+members = [\"crate-a\"]
+
+[workspace.dependencies]
+serde = \"1.0\"
+",
+        );
+        let member = manifest(
+            "crate-a/Cargo.toml",
+            "\
+[package]
+name = \"crate-a\"
+
+[dependencies]
+serde = \"0.9\"
+local = { path = \"../local\" }
+remote = { git = \"https://example.com/remote\" }
+tokio = { workspace = true }
+",
+        );
+        let input = member.doc.to_string();
+        let mut manifests = vec![root, member];
+        hoist_workspace_dependencies(&mut manifests, 0);
+
+        assert_eq!(manifests[0].change_count(), 0);
+        assert_eq!(manifests[1].change_count(), 0);
+        assert_eq!(manifests[1].doc.to_string(), input);
+    }
+
+    #[test]
+    fn hoist_creates_workspace_dependencies_in_virtual_root() {
+        let root = manifest(
+            "Cargo.toml",
+            "\
+[workspace]
+members = [\"crate-a\"]
+resolver = \"3\"
+",
+        );
+        let member = manifest(
+            "crate-a/Cargo.toml",
+            "\
+[package]
+name = \"crate-a\"
+
+[dependencies.anyhow]
+version = \"1.0\"
+",
+        );
+        let mut manifests = vec![root, member];
+        hoist_workspace_dependencies(&mut manifests, 0);
+
+        let root = manifests[0].render().expect("valid root");
+        let member = manifests[1].render().expect("valid member");
+        assert!(
+            root.contains("[workspace.dependencies]\nanyhow = \"1.0\""),
+            "root:\n{root}"
+        );
+        assert!(
+            member.contains("anyhow = { workspace = true }"),
+            "member:\n{member}"
+        );
+    }
+
+    #[test]
+    fn hoist_skips_manifests_without_workspace() {
+        let single = manifest(
+            "Cargo.toml",
+            "\
+[package]
+name = \"single\"
+
+[dependencies]
+anyhow = \"1.0\"
+",
+        );
+        let mut manifests = vec![single];
+        hoist_workspace_dependencies(&mut manifests, 0);
+
+        assert_eq!(manifests[0].change_count(), 0);
+        assert!(manifests[0].doc.get("workspace").is_none());
+    }
+
+    #[test]
+    fn package_fields_inherit_matching_workspace_values() {
+        let root = manifest(
+            "Cargo.toml",
+            "\
+[workspace]
+members = [\"crate-a\"]
+
+[workspace.package]
+version = \"0.2.0\"
+edition = \"2024\"
+authors = [\"Jane <jane@example.com>\"]
+license-file = \"LICENSE\"
+rust-version = \"1.85\"
+",
+        );
+        let member = manifest(
+            "crate-a/Cargo.toml",
+            "\
+[package]
+name = \"crate-a\"
+version = \"0.2.0\" # released
+edition.workspace = true
+authors = [
+    \"Jane <jane@example.com>\",
+]
+license-file = \"../LICENSE\"
+rust-version = \"1.80\"
+",
+        );
+        let mut manifests = vec![root, member];
+        inherit_package_fields(&mut manifests, 0);
+
+        assert_eq!(manifests[0].change_count(), 0);
+        assert_eq!(manifests[1].change_count(), 3);
+        assert_eq!(
+            manifests[1].doc.to_string(),
+            "\
+[package]
+name = \"crate-a\"
+version = { workspace = true } # released
+edition.workspace = true
+authors = { workspace = true }
+license-file = { workspace = true }
+rust-version = \"1.80\"
+"
+        );
+        assert_eq!(
+            warning_messages(&manifests[1]),
+            ["Kept [package] rust-version: it differs from [workspace.package]"]
+        );
+    }
+
+    #[test]
+    fn differing_package_fields_need_attention() {
+        let root = manifest(
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"crate-a\"]\n\n[workspace.package]\nedition = \"2024\"\n",
+        );
+        let member = manifest(
+            "crate-a/Cargo.toml",
+            "[package]\nname = \"crate-a\"\nedition = \"2021\"\n",
+        );
+        let mut manifests = vec![root, member];
+        inherit_package_fields(&mut manifests, 0);
+
+        assert_eq!(manifests[1].change_count(), 0);
+        assert_eq!(
+            warning_messages(&manifests[1]),
+            ["Kept [package] edition: it differs from [workspace.package]"]
+        );
+        assert!(manifests[1].needs_attention());
+        assert!(!manifests[0].needs_attention());
+    }
+
+    #[test]
+    fn internal_path_dependencies_inherit_from_workspace() {
+        let root = manifest(
+            "Cargo.toml",
+            "\
+[workspace]
+members = [\"crates/*\"]
+",
+        );
+        let member = manifest(
+            "crates/app/Cargo.toml",
+            "\
+[package]
+name = \"app\"
+
+[dependencies]
+core = { path = \"../core\", features = [\"std\"] }
+util = { path = \"../util\", version = \"0.2\" }
+vendored = { path = \"../../vendor/vendored\" }
+",
+        );
+        let members = [
+            WorkspaceMember {
+                dir: PathBuf::from("crates/core"),
+                version: "0.1.0".to_string(),
+            },
+            WorkspaceMember {
+                dir: PathBuf::from("crates/util"),
+                version: "0.2.3".to_string(),
+            },
+        ];
+        let mut manifests = vec![root, member];
+        inherit_internal_dependencies(&mut manifests, 0, &members);
+
+        let root = manifests[0].doc.to_string();
+        let member = manifests[1].doc.to_string();
+        assert_eq!(manifests[0].change_count(), 2, "root:\n{root}");
+        assert_eq!(manifests[1].change_count(), 2, "member:\n{member}");
+        assert_eq!(
+            manifests[0].changes[0].message,
+            "Added internal dependencies to [workspace.dependencies]"
+        );
+        assert!(
+            root.contains("core = { path = \"crates/core\", version = \"0.1.0\" }"),
+            "root:\n{root}"
+        );
+        assert!(
+            root.contains("util = { path = \"crates/util\", version = \"0.2\" }"),
+            "root:\n{root}"
+        );
+        assert!(
+            member.contains("core = { workspace = true, features = [\"std\"] }"),
+            "member:\n{member}"
+        );
+        assert!(
+            member.contains("util = { workspace = true }"),
+            "member:\n{member}"
+        );
+        assert!(
+            member.contains("vendored = { path = \"../../vendor/vendored\" }"),
+            "member:\n{member}"
+        );
+    }
+
+    #[test]
+    fn internal_dependencies_reuse_existing_workspace_entry() {
+        let root = manifest(
+            "Cargo.toml",
+            "\
+[workspace]
+members = [\"crates/*\"]
+
+[workspace.dependencies]
+core = { path = \"./crates/core\" }
+",
+        );
+        let member = manifest(
+            "crates/app/Cargo.toml",
+            "\
+[package]
+name = \"app\"
+
+[dev-dependencies]
+core = { path = \"../core\" }
+",
+        );
+        let members = [WorkspaceMember {
+            dir: PathBuf::from("crates/core"),
+            version: "0.1.0".to_string(),
+        }];
+        let mut manifests = vec![root, member];
+        inherit_internal_dependencies(&mut manifests, 0, &members);
+
+        let root = manifests[0].doc.to_string();
+        let member = manifests[1].doc.to_string();
+        assert_eq!(manifests[0].change_count(), 0, "root:\n{root}");
+        assert_eq!(manifests[1].change_count(), 1, "member:\n{member}");
+        assert!(
+            member.contains("core = { workspace = true }"),
+            "member:\n{member}"
+        );
+    }
+
+    /// Write a workspace with a member in `crates/app` to a fresh
+    /// temporary directory and return the directory.
+    fn write_workspace(name: &str, root: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("fmt-toml-{name}-{}", std::process::id()));
+        let member_dir = dir.join("crates").join("app");
+        std::fs::create_dir_all(member_dir.join("src")).expect("create temp dir");
+        std::fs::write(dir.join("Cargo.toml"), root).expect("write root manifest");
+        std::fs::write(
+            member_dir.join("Cargo.toml"),
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2024\"\n",
+        )
+        .expect("write member manifest");
+        std::fs::write(member_dir.join("src").join("lib.rs"), "").expect("write member source");
+        dir
+    }
+
+    #[test]
+    fn load_includes_a_virtual_root() {
+        let dir = write_workspace(
+            "virtual-root",
+            "[workspace]\nmembers = [\"crates/app\"]\nresolver = \"3\"\n",
+        );
+        let workspace = Workspace::load(&dir);
+        std::fs::remove_dir_all(&dir).expect("remove temp dir");
+
+        // cargo reports canonical paths, so compare the trailing
+        // components only.
+        let root_manifest = Path::new(dir.file_name().expect("named")).join("Cargo.toml");
+        let workspace = workspace.expect("loads the workspace");
+        assert_eq!(workspace.manifests.len(), 2);
+        assert!(
+            workspace.manifests[0]
+                .path
+                .ends_with("crates/app/Cargo.toml")
+        );
+        let root_index = workspace.root_index.expect("root is known");
+        assert!(
+            workspace.manifests[root_index]
+                .path
+                .ends_with(&root_manifest)
+        );
+    }
+
+    #[test]
+    fn load_includes_a_package_root_once() {
+        let dir = write_workspace(
+            "package-root",
+            "\
+[package]
+name = \"root\"
+version = \"0.1.0\"
+edition = \"2024\"
+
+[workspace]
+members = [\"crates/app\"]
+",
+        );
+        std::fs::create_dir_all(dir.join("src")).expect("create root source dir");
+        std::fs::write(dir.join("src").join("lib.rs"), "").expect("write root source");
+        let workspace = Workspace::load(&dir);
+        std::fs::remove_dir_all(&dir).expect("remove temp dir");
+
+        let root_manifest = Path::new(dir.file_name().expect("named")).join("Cargo.toml");
+        let workspace = workspace.expect("loads the workspace");
+        let root_count = workspace
+            .manifests
+            .iter()
+            .filter(|m| m.path.ends_with(&root_manifest))
+            .count();
+        assert_eq!(workspace.manifests.len(), 2);
+        assert_eq!(root_count, 1);
+        let root_index = workspace.root_index.expect("root is known");
+        assert!(
+            workspace.manifests[root_index]
+                .path
+                .ends_with(&root_manifest)
+        );
+    }
+
+    #[test]
+    fn load_files_accepts_a_bare_file_name() {
+        // Tests run from the package root, next to its Cargo.toml.
+        let workspace =
+            Workspace::load_files(&[PathBuf::from("Cargo.toml")]).expect("loads the manifest");
+        assert_eq!(workspace.manifests.len(), 1);
+        assert_eq!(manifest_dir(Path::new("Cargo.toml")), Path::new("."));
+        assert_eq!(
+            manifest_dir(Path::new("crates/app/Cargo.toml")),
+            Path::new("crates/app")
+        );
+    }
+
+    #[test]
+    fn load_files_keeps_manifests_that_fail_to_parse() {
+        let dir = write_workspace(
+            "broken-member",
+            "[workspace]\nmembers = [\"crates/app\"]\nresolver = \"3\"\n",
+        );
+        let member = dir.join("crates").join("app").join("Cargo.toml");
+        std::fs::write(&member, "[package\nname = \"app\"\n").expect("write member manifest");
+        let workspace = Workspace::load_files(&[member, dir.join("Cargo.toml")]);
+        std::fs::remove_dir_all(&dir).expect("remove temp dir");
+
+        let mut workspace = workspace.expect("loads the manifests");
+        assert_eq!(workspace.manifests.len(), 2);
+        let config = workspace.config.clone();
+        for manifest in workspace.manifests.iter_mut() {
+            manifest.format(&config);
+        }
+        let error = workspace.manifests[0]
+            .error
+            .as_ref()
+            .expect("member failed");
+        assert!(
+            format!("{error:#}").contains("Failed to parse"),
+            "error: {error:#}"
+        );
+        assert!(workspace.manifests[1].error.is_none());
+    }
+
+    #[test]
+    fn workspace_rules_run_before_the_per_manifest_rules() {
+        let workspace_ids: Vec<&str> = Workspace::rules().iter().map(|rule| rule.id()).collect();
+        let manifest_ids: Vec<&str> = crate::rules::all().iter().map(|rule| rule.id()).collect();
+        assert_eq!(
+            [workspace_ids, manifest_ids].concat(),
+            crate::config::RULE_IDS
+        );
+    }
+
+    #[test]
+    fn internal_dependencies_keep_a_differing_version() {
+        let root = manifest(
+            "Cargo.toml",
+            "\
+[workspace]
+members = [\"crates/*\"]
+
+[workspace.dependencies]
+core = { path = \"crates/core\", version = \"0.2\" }
+util = { path = \"crates/util\" }
+",
+        );
+        let member = manifest(
+            "crates/app/Cargo.toml",
+            "\
+[package]
+name = \"app\"
+
+[dependencies]
+core = { path = \"../core\", version = \"0.1\" }
+util = { path = \"../util\", version = \"0.3\" }
+",
+        );
+        let members = [
+            WorkspaceMember {
+                dir: PathBuf::from("crates/core"),
+                version: "0.2.1".to_string(),
+            },
+            WorkspaceMember {
+                dir: PathBuf::from("crates/util"),
+                version: "0.3.0".to_string(),
+            },
+        ];
+        let input = member.doc.to_string();
+        let mut manifests = vec![root, member];
+        inherit_internal_dependencies(&mut manifests, 0, &members);
+
+        assert_eq!(manifests[1].change_count(), 0);
+        assert_eq!(manifests[1].doc.to_string(), input);
+        assert_eq!(
+            warning_messages(&manifests[1]),
+            [
+                "Kept core: it differs from [workspace.dependencies]",
+                "Kept util: it differs from [workspace.dependencies]",
+            ]
+        );
+        assert!(!manifests[1].needs_attention());
+    }
+
+    #[test]
+    fn normalize_path_resolves_relative_components() {
+        assert_eq!(
+            normalize_path(Path::new("crates/app/../core/./src")),
+            PathBuf::from("crates/core/src")
+        );
+        assert_eq!(
+            normalize_path(Path::new("../vendor")),
+            PathBuf::from("../vendor")
+        );
+    }
+}